@@ -2,15 +2,16 @@ use std::str::FromStr;
 
 use anyhow::{Error, Result};
 use chrono::NaiveDate;
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, CommandFactory, Parser, Subcommand, error::ErrorKind};
+
+use crate::minutes::{Heuristic, MinutesFormatKind};
 
 /// Comment github issues with links to meeting minutes
 #[derive(Parser, Debug)]
 #[command(version, about)]
 pub struct CmdArgs {
-    /// Github token used to create comments
-    #[arg(short, long, env = "M2G_TOKEN")]
-    pub token: String,
+    #[command(flatten)]
+    pub github: GitHubArgs,
 
     /// Log-level (error, warn, info, debug, trace)
     #[arg(
@@ -27,21 +28,139 @@ pub struct CmdArgs {
     pub subcommand: SubCmdArgs,
 }
 
+impl CmdArgs {
+    /// Check the constraints between arguments that cannot be expressed with clap attributes.
+    pub fn validate(&self) -> Result<(), clap::Error> {
+        if let SubCmdArgs::Manual(args) = &self.subcommand
+            && let (Some(from), Some(to)) = (args.from, args.to)
+            && to < from
+        {
+            return Err(Self::command().error(
+                ErrorKind::ArgumentConflict,
+                format!("--to ({to}) must not be earlier than --from ({from})"),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Options for connecting to GitHub
+#[derive(Args, Clone, Debug)]
+pub struct GitHubArgs {
+    /// Github token used to create comments
+    #[arg(
+        short,
+        long,
+        env = "M2G_TOKEN",
+        required_unless_present = "app_id",
+        conflicts_with = "app_id"
+    )]
+    pub token: Option<String>,
+
+    /// Id of the GitHub App used to create comments (instead of a token)
+    #[arg(
+        long,
+        env = "M2G_APP_ID",
+        requires = "app_private_key",
+        help_heading = "Advanced options",
+        hide_short_help = true
+    )]
+    pub app_id: Option<u64>,
+
+    /// File containing the private key (PEM) of the GitHub App
+    #[arg(
+        long,
+        env = "M2G_APP_PRIVATE_KEY",
+        help_heading = "Advanced options",
+        hide_short_help = true
+    )]
+    pub app_private_key: Option<String>,
+
+    /// Base URL of the GitHub REST API, e.g. 'https://github.example.org/api/v3' for GitHub Enterprise Server
+    /// (default: https://api.github.com, or derived from --github-host)
+    #[arg(
+        long,
+        env = "M2G_GITHUB_API_URL",
+        help_heading = "Advanced options",
+        hide_short_help = true
+    )]
+    pub github_api_url: Option<String>,
+
+    /// Host of the GitHub web interface, against which links to issues are recognized
+    #[arg(
+        long,
+        env = "M2G_GITHUB_HOST",
+        default_value = "github.com",
+        help_heading = "Advanced options",
+        hide_short_help = true
+    )]
+    pub github_host: String,
+}
+
+impl GitHubArgs {
+    /// The base URL of the REST API.
+    pub fn api_url(&self) -> String {
+        match &self.github_api_url {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None if self.github_host == "github.com" => "https://api.github.com".into(),
+            None => format!("https://{}/api/v3", self.github_host),
+        }
+    }
+
+    /// The base URL of the GraphQL API (which lives at `/graphql` under it).
+    ///
+    /// On GitHub Enterprise Server, the REST API is under `/api/v3` while the GraphQL API is at `/api/graphql`.
+    pub fn graphql_base_url(&self) -> String {
+        let api_url = self.api_url();
+        match api_url.strip_suffix("/v3") {
+            Some(base) => base.to_string(),
+            None => api_url,
+        }
+    }
+}
+
+/// The default base URL of the minutes (see [`EngineArgs::minutes_url`])
+pub const MINUTES_URL: &str = "https://www.w3.org";
+
+/// The default base URL of the w3c/groups repository (see [`EngineArgs::groups_url`])
+pub const GROUPS_URL: &str = "https://raw.githubusercontent.com/w3c/groups/refs/heads/main";
+
 /// Subcommands
 #[derive(Subcommand, Clone, Debug)]
 pub enum SubCmdArgs {
     /// Run an IRC bot that can comment github issues
     IrcBot(IrcBotArgs),
     /// Comment github issues from the command line
-    Manual(EngineArgs),
+    Manual(ManualArgs),
+    /// Delete the comments created by a previous run
+    Revert(RevertArgs),
 }
 
 /// See [`SubCmdArgs::Manual`]
 #[derive(Args, Clone, Debug)]
+pub struct ManualArgs {
+    #[command(flatten)]
+    pub engine: EngineArgs,
+
+    /// Process the minutes of every day from this date (formatted as YYYY-MM-DD), instead of a single date
+    #[arg(long, env = "M2G_FROM", conflicts_with_all = ["date", "url", "file"])]
+    pub from: Option<NaiveDate>,
+
+    /// Last date to process with --from (default: today)
+    #[arg(long, env = "M2G_TO", requires = "from")]
+    pub to: Option<NaiveDate>,
+
+    /// Process all the minutes listed in the given JSON file, instead of a single date
+    #[arg(long, env = "M2G_MANIFEST", conflicts_with_all = ["date", "url", "file", "from"])]
+    pub manifest: Option<String>,
+}
+
+/// Options of the engine, used in manual mode and (partly) by the IRC bot
+#[derive(Args, Clone, Debug)]
 pub struct EngineArgs {
-    /// IRC channel from where the minutes were generated
-    #[arg(short, long, env = "M2G_CHANNEL")]
-    pub channel: String,
+    /// IRC channel from where the minutes were generated (with --manifest, the default for all entries)
+    #[arg(short, long, env = "M2G_CHANNEL", required_unless_present = "manifest")]
+    pub channel: Option<String>,
 
     /// Date of the minutes, formatted as YYYY-MM-DD
     #[arg(short, long, env = "M2G_DATE")]
@@ -51,6 +170,14 @@ pub struct EngineArgs {
     #[arg(short = 'T', long, env = "M2G_TRANSCRIPT", default_value_t = false)]
     pub transcript: bool,
 
+    /// Include resolutions and action items in GitHub comment
+    #[arg(short = 'R', long, env = "M2G_RESOLUTIONS", default_value_t = false)]
+    pub resolutions: bool,
+
+    /// Mention in GitHub comment the participants who spoke in the relevant section (requires --participants)
+    #[arg(long, env = "M2G_MENTION_PARTICIPANTS", default_value_t = false)]
+    pub mention_participants: bool,
+
     /// Comma-separated list of groups concerned by these minutes (defaults to "wg/{channel}")
     #[arg(short, long, env = "M2G_GROUP")]
     pub groups: Option<String>,
@@ -59,6 +186,20 @@ pub struct EngineArgs {
     #[arg(short, long, env = "M2G_RATE_LIMIT", default_value_t = FinitePositiveF64(0.2), value_parser = FinitePositiveF64::from_str, help_heading = "Advanced options", hide_short_help = true)]
     pub rate_limit: FinitePositiveF64,
 
+    /// Do not comment issues, pull requests and discussions that are closed
+    #[arg(long, env = "M2G_SKIP_CLOSED", default_value_t = false)]
+    pub skip_closed: bool,
+
+    /// Number of days before the meeting from which existing comments are searched (to avoid duplicates)
+    #[arg(
+        long,
+        env = "M2G_LOOK_BACK",
+        default_value_t = 1,
+        help_heading = "Advanced options",
+        hide_short_help = true
+    )]
+    pub look_back: u32,
+
     /// Do not actually perform the operations on GitHub
     #[arg(
         short = 'n',
@@ -68,6 +209,10 @@ pub struct EngineArgs {
     )]
     pub dry_run: bool,
 
+    /// IRC nickname of the person who requested this run (`None` when run from the command line)
+    #[arg(skip)]
+    pub requested_by: Option<String>,
+
     /// URL to read minutes from (default: constructed from channel and date)
     #[arg(
         long,
@@ -77,6 +222,26 @@ pub struct EngineArgs {
     )]
     pub url: Option<String>,
 
+    /// Base URL under which the minutes are published, used when --url is not provided
+    #[arg(
+        long,
+        env = "M2G_MINUTES_URL",
+        default_value = MINUTES_URL,
+        help_heading = "Advanced options",
+        hide_short_help = true
+    )]
+    pub minutes_url: String,
+
+    /// Base URL of the w3c/groups repository, from which the repositories owned by each group are retrieved
+    #[arg(
+        long,
+        env = "M2G_GROUPS_URL",
+        default_value = GROUPS_URL,
+        help_heading = "Advanced options",
+        hide_short_help = true
+    )]
+    pub groups_url: String,
+
     /// File to read minutes from (default: fetched from URL)
     #[arg(
         long,
@@ -86,6 +251,25 @@ pub struct EngineArgs {
     )]
     pub file: Option<String>,
 
+    /// Format of the minutes (default: guessed from the file or URL, and from the content)
+    #[arg(
+        long,
+        env = "M2G_FORMAT",
+        help_heading = "Advanced options",
+        hide_short_help = true
+    )]
+    pub format: Option<MinutesFormatKind>,
+
+    /// Heuristic used to locate the heading corresponding to an issue mentioned in HTML minutes
+    #[arg(
+        long,
+        env = "M2G_HEURISTIC",
+        default_value = "relevant",
+        help_heading = "Advanced options",
+        hide_short_help = true
+    )]
+    pub heuristic: Heuristic,
+
     /// Allowed repository (in addition to those belonging to the group)
     ///
     /// The format of this argument is either '{org}/{repo}' or '{repo}'.
@@ -96,6 +280,131 @@ pub struct EngineArgs {
         hide_short_help = true
     )]
     pub extra_repositories: Vec<String>,
+
+    /// Repository against which bare issue numbers (e.g. '#123') are resolved
+    ///
+    /// The format of this argument is either '{org}/{repo}' or '{repo}'.
+    /// In the latter case, the organization is assumed to be `w3c`.
+    /// If not provided, bare issue numbers are ignored.
+    #[arg(
+        long,
+        env = "M2G_DEFAULT_REPOSITORY",
+        help_heading = "Advanced options",
+        hide_short_help = true
+    )]
+    pub default_repository: Option<String>,
+
+    /// JSON file mapping IRC nicks or W3C names to GitHub logins (see --mention-participants)
+    #[arg(
+        long,
+        env = "M2G_PARTICIPANTS",
+        help_heading = "Advanced options",
+        hide_short_help = true
+    )]
+    pub participants: Option<String>,
+
+    #[command(flatten)]
+    pub templates: TemplateArgs,
+
+    #[command(flatten)]
+    pub labels: LabelArgs,
+
+    #[command(flatten)]
+    pub records: RecordArgs,
+}
+
+/// See [`SubCmdArgs::Revert`]
+#[derive(Args, Clone, Debug)]
+pub struct RevertArgs {
+    /// Id of the run to revert (as displayed in the logs, and used as the name of its record)
+    pub run_id: String,
+
+    /// Directory where the records of the runs are stored
+    #[arg(long, env = "M2G_RUNS_DIR")]
+    pub runs_dir: String,
+}
+
+/// Options for recording runs
+#[derive(Args, Clone, Debug, Default)]
+pub struct RecordArgs {
+    /// Directory where to record the comments created by each run, so that they can be reverted
+    #[arg(
+        long,
+        env = "M2G_RUNS_DIR",
+        help_heading = "Advanced options",
+        hide_short_help = true
+    )]
+    pub runs_dir: Option<String>,
+
+    /// File (in JSON Lines) to which every run and its outcomes are appended, for audit purposes
+    #[arg(
+        long,
+        env = "M2G_LEDGER",
+        help_heading = "Advanced options",
+        hide_short_help = true
+    )]
+    pub ledger: Option<String>,
+}
+
+/// Options for updating the labels of issues once they have been commented
+#[derive(Args, Clone, Debug, Default)]
+pub struct LabelArgs {
+    /// Label to add to issues and pull requests once they have been commented
+    ///
+    /// The label may contain '{date}', which will be replaced by the date of the meeting (YYYY-MM-DD),
+    /// e.g. 'discussed-{date}'.
+    #[arg(
+        long,
+        env = "M2G_ADD_LABEL",
+        help_heading = "Advanced options",
+        hide_short_help = true
+    )]
+    pub add_label: Option<String>,
+
+    /// Labels to remove from issues and pull requests once they have been commented (comma separated), e.g. 'agenda+'
+    #[arg(
+        long = "remove-label",
+        env = "M2G_REMOVE_LABELS",
+        value_delimiter = ',',
+        help_heading = "Advanced options",
+        hide_short_help = true
+    )]
+    pub remove_labels: Vec<String>,
+}
+
+/// Options for customizing the comments posted on GitHub
+#[derive(Args, Clone, Debug, Default)]
+pub struct TemplateArgs {
+    /// File containing the template of GitHub comments
+    ///
+    /// Supported placeholders are {url} (mandatory), {date}, {groups}, {topic}, {channel}, {sections},
+    /// {transcript}, {resolutions}, {participants} and {run_id}; literal braces are written '{{' and '}}'.
+    #[arg(
+        long,
+        env = "M2G_TEMPLATE",
+        help_heading = "Advanced options",
+        hide_short_help = true
+    )]
+    pub template: Option<String>,
+
+    /// Template of GitHub comments for a given group, formatted as '{group}={file}'
+    ///
+    /// When several groups are concerned by the minutes, the template of the first one having one is used.
+    #[arg(
+        long = "group-template",
+        value_parser = parse_group_template,
+        help_heading = "Advanced options",
+        hide_short_help = true
+    )]
+    pub group_templates: Vec<(String, String)>,
+}
+
+/// Parse a '{group}={file}' pair.
+fn parse_group_template(s: &str) -> Result<(String, String)> {
+    let (group, file) = s
+        .split_once('=')
+        .ok_or_else(|| Error::msg(format!("{s:?} is not of the form {{group}}={{file}}")))?;
+    Ok((group.to_string(), file.to_string()))
 }
 
 /// See [`SubCmdArgs::IrcBot`]
@@ -124,6 +433,48 @@ pub struct IrcBotArgs {
     /// Channels on which the bot should connect automatically (comma separated)
     #[arg(short, long, env = "M2G_CHANNELS")]
     pub channels: Vec<String>,
+
+    /// Repository against which bare issue numbers (e.g. '#123') are resolved on a given channel
+    ///
+    /// The format of this argument is '{channel}={org}/{repo}' or '{channel}={repo}' (comma separated).
+    /// In the latter case, the organization is assumed to be `w3c`.
+    #[arg(
+        long = "default-repository",
+        env = "M2G_DEFAULT_REPOSITORIES",
+        value_delimiter = ',',
+        value_parser = parse_channel_repository
+    )]
+    pub default_repositories: Vec<(String, String)>,
+
+    /// Do not comment issues, pull requests and discussions that are closed
+    #[arg(long, env = "M2G_SKIP_CLOSED", default_value_t = false)]
+    pub skip_closed: bool,
+
+    /// JSON file mapping IRC nicks or W3C names to GitHub logins
+    ///
+    /// Participants are only mentioned in GitHub comments when requested ("with participants").
+    #[arg(long, env = "M2G_PARTICIPANTS")]
+    pub participants: Option<String>,
+
+    #[command(flatten)]
+    pub templates: TemplateArgs,
+
+    #[command(flatten)]
+    pub labels: LabelArgs,
+
+    #[command(flatten)]
+    pub records: RecordArgs,
+}
+
+/// Parse a '{channel}={repo}' pair, normalizing the channel name without its leading '#'.
+fn parse_channel_repository(s: &str) -> Result<(String, String)> {
+    let (channel, repo) = s
+        .split_once('=')
+        .ok_or_else(|| Error::msg(format!("{s:?} is not of the form {{channel}}={{repo}}")))?;
+    Ok((
+        channel.trim_start_matches('#').to_string(),
+        repo.to_string(),
+    ))
 }
 
 impl From<IrcBotArgs> for irc::client::prelude::Config {
@@ -185,3 +536,42 @@ impl From<FinitePositiveF64> for f64 {
         wrapper.0
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(None, "github.com" => ("https://api.github.com".to_string(), "https://api.github.com".to_string()))]
+    #[test_case(None, "ghe.example.org" => ("https://ghe.example.org/api/v3".to_string(), "https://ghe.example.org/api".to_string()))]
+    #[test_case(Some("http://localhost:8080/"), "github.com" => ("http://localhost:8080".to_string(), "http://localhost:8080".to_string()))]
+    fn api_urls(api_url: Option<&str>, host: &str) -> (String, String) {
+        let args = GitHubArgs {
+            token: None,
+            app_id: None,
+            app_private_key: None,
+            github_api_url: api_url.map(ToString::to_string),
+            github_host: host.into(),
+        };
+        (args.api_url(), args.graphql_base_url())
+    }
+
+    #[test_case("2024-11-14" => true)]
+    #[test_case("2024-11-15" => true)]
+    #[test_case("2024-11-13" => false)]
+    fn validate_to(to: &str) -> bool {
+        let args = [
+            "minutes_to_gh",
+            "--token",
+            "secret",
+            "manual",
+            "--channel",
+            "vc",
+            "--from",
+            "2024-11-14",
+            "--to",
+            to,
+        ];
+        CmdArgs::parse_from(args).validate().is_ok()
+    }
+}
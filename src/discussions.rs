@@ -0,0 +1,301 @@
+//! I define types to handle GitHub Discussions, which are only accessible through the GraphQL API.
+
+use anyhow::{Error, Result};
+use octocrab::Octocrab;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+
+/// A GitHub discussion, with its comments.
+#[derive(Debug)]
+pub struct Discussion {
+    /// The GraphQL node id of this discussion
+    pub id: String,
+    pub comments: Vec<DiscussionComment>,
+}
+
+/// A comment in a GitHub discussion.
+#[derive(Clone, Debug, Deserialize)]
+pub struct DiscussionComment {
+    /// The GraphQL node id of this comment
+    pub id: String,
+    pub url: String,
+    pub body: String,
+    /// The author of this comment (`None` if the account was deleted)
+    pub author: Option<CommentAuthor>,
+}
+
+/// The author of a [`DiscussionComment`].
+#[derive(Clone, Debug, Deserialize)]
+pub struct CommentAuthor {
+    pub login: String,
+}
+
+/// The status of a GitHub discussion, as returned by [`Discussion::status`].
+#[derive(Debug)]
+pub struct DiscussionStatus {
+    pub url: String,
+    /// Whether the repository of this discussion is archived
+    pub archived: bool,
+    pub locked: bool,
+    pub closed: bool,
+}
+
+impl Discussion {
+    /// Retrieve the given discussion, with all its (top-level) comments.
+    pub async fn fetch(github: &Octocrab, owner: &str, repo: &str, number: u64) -> Result<Self> {
+        const QUERY: &str = r"
+            query($owner: String!, $repo: String!, $number: Int!, $cursor: String) {
+              repository(owner: $owner, name: $repo) {
+                discussion(number: $number) {
+                  id
+                  comments(first: 100, after: $cursor) {
+                    pageInfo { hasNextPage endCursor }
+                    nodes { id url body author { login } }
+                  }
+                }
+              }
+            }";
+
+        let mut id = None;
+        let mut comments = vec![];
+        let mut cursor: Option<String> = None;
+        loop {
+            let data: RepositoryData = graphql(
+                github,
+                QUERY,
+                json!({ "owner": owner, "repo": repo, "number": number, "cursor": cursor }),
+            )
+            .await?;
+            let discussion = data.repository.and_then(|r| r.discussion).ok_or_else(|| {
+                Error::msg(format!("Discussion {owner}/{repo}#{number} not found"))
+            })?;
+            id.get_or_insert(discussion.id);
+            comments.extend(discussion.comments.nodes);
+            if !discussion.comments.page_info.has_next_page {
+                break;
+            }
+            cursor = discussion.comments.page_info.end_cursor;
+        }
+        Ok(Self {
+            id: id.unwrap(),
+            comments,
+        })
+    }
+
+    /// The current status of the given discussion, or `None` if it does not exist.
+    ///
+    /// The URL of the discussion may differ from the one given if the repository was renamed.
+    pub async fn status(
+        github: &Octocrab,
+        owner: &str,
+        repo: &str,
+        number: u64,
+    ) -> Result<Option<DiscussionStatus>> {
+        const QUERY: &str = r"
+            query($owner: String!, $repo: String!, $number: Int!) {
+              repository(owner: $owner, name: $repo) {
+                isArchived
+                discussion(number: $number) { url locked closed }
+              }
+            }";
+
+        let variables = json!({ "owner": owner, "repo": repo, "number": number });
+        let data: Option<StatusData> = graphql_optional(github, QUERY, variables).await?;
+        let found = data
+            .and_then(|d| d.repository)
+            .and_then(|r| Some((r.is_archived, r.discussion?)));
+        Ok(found.map(|(archived, d)| DiscussionStatus {
+            url: d.url,
+            archived,
+            locked: d.locked,
+            closed: d.closed,
+        }))
+    }
+
+    /// Add a comment to this discussion.
+    pub async fn add_comment(&self, github: &Octocrab, body: &str) -> Result<DiscussionComment> {
+        const MUTATION: &str = r"
+            mutation($id: ID!, $body: String!) {
+              addDiscussionComment(input: {discussionId: $id, body: $body}) {
+                comment { id url body author { login } }
+              }
+            }";
+
+        let data: AddCommentData =
+            graphql(github, MUTATION, json!({ "id": self.id, "body": body })).await?;
+        Ok(data.add_discussion_comment.comment)
+    }
+
+    /// Retrieve the body of the discussion comment with the given GraphQL node id, or `None` if it does not exist.
+    pub async fn comment_body(github: &Octocrab, id: &str) -> Result<Option<String>> {
+        const QUERY: &str = r"
+            query($id: ID!) {
+              node(id: $id) { ... on DiscussionComment { body } }
+            }";
+
+        let data: Option<NodeData> = graphql_optional(github, QUERY, json!({ "id": id })).await?;
+        Ok(data.and_then(|d| d.node).and_then(|n| n.body))
+    }
+
+    /// Delete the discussion comment with the given GraphQL node id.
+    pub async fn delete_comment(github: &Octocrab, id: &str) -> Result<()> {
+        const MUTATION: &str = r"
+            mutation($id: ID!) {
+              deleteDiscussionComment(input: {id: $id}) { clientMutationId }
+            }";
+
+        let _: serde_json::Value = graphql(github, MUTATION, json!({ "id": id })).await?;
+        Ok(())
+    }
+
+    /// Replace the body of the discussion comment with the given GraphQL node id.
+    pub async fn update_comment(
+        github: &Octocrab,
+        id: &str,
+        body: &str,
+    ) -> Result<DiscussionComment> {
+        const MUTATION: &str = r"
+            mutation($id: ID!, $body: String!) {
+              updateDiscussionComment(input: {commentId: $id, body: $body}) {
+                comment { id url body author { login } }
+              }
+            }";
+
+        let data: UpdateCommentData =
+            graphql(github, MUTATION, json!({ "id": id, "body": body })).await?;
+        Ok(data.update_discussion_comment.comment)
+    }
+}
+
+/// Send a GraphQL request, and convert GraphQL errors into [`anyhow::Error`]s.
+async fn graphql<T: DeserializeOwned>(
+    github: &Octocrab,
+    query: &str,
+    variables: serde_json::Value,
+) -> Result<T> {
+    request(github, query, variables, false)
+        .await?
+        .ok_or_else(|| Error::msg("GraphQL response without data"))
+}
+
+/// Like [`graphql`], but `NOT_FOUND` errors are not converted into [`anyhow::Error`]s,
+/// so the data may be missing, or contain null for the nodes that were not found.
+async fn graphql_optional<T: DeserializeOwned>(
+    github: &Octocrab,
+    query: &str,
+    variables: serde_json::Value,
+) -> Result<Option<T>> {
+    request(github, query, variables, true).await
+}
+
+async fn request<T: DeserializeOwned>(
+    github: &Octocrab,
+    query: &str,
+    variables: serde_json::Value,
+    allow_not_found: bool,
+) -> Result<Option<T>> {
+    let response: GraphQlResponse<T> = github
+        .graphql(&json!({ "query": query, "variables": variables }))
+        .await?;
+    let errors = response.errors.unwrap_or_default();
+    let not_found = |e: &GraphQlError| allow_not_found && e.kind.as_deref() == Some("NOT_FOUND");
+    if errors.iter().all(not_found) {
+        return Ok(response.data);
+    }
+    let messages: Vec<_> = errors.into_iter().map(|e| e.message).collect();
+    Err(Error::msg(format!(
+        "GraphQL error: {}",
+        messages.join("; ")
+    )))
+}
+
+#[derive(Deserialize)]
+struct GraphQlResponse<T> {
+    data: Option<T>,
+    errors: Option<Vec<GraphQlError>>,
+}
+
+#[derive(Deserialize)]
+struct GraphQlError {
+    message: String,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RepositoryData {
+    repository: Option<RepositoryNode>,
+}
+
+#[derive(Deserialize)]
+struct RepositoryNode {
+    discussion: Option<DiscussionNode>,
+}
+
+#[derive(Deserialize)]
+struct DiscussionNode {
+    id: String,
+    comments: CommentConnection,
+}
+
+#[derive(Deserialize)]
+struct CommentConnection {
+    #[serde(rename = "pageInfo")]
+    page_info: PageInfo,
+    nodes: Vec<DiscussionComment>,
+}
+
+#[derive(Deserialize)]
+struct PageInfo {
+    #[serde(rename = "hasNextPage")]
+    has_next_page: bool,
+    #[serde(rename = "endCursor")]
+    end_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StatusData {
+    repository: Option<StatusRepository>,
+}
+
+#[derive(Deserialize)]
+struct StatusRepository {
+    #[serde(rename = "isArchived")]
+    is_archived: bool,
+    discussion: Option<StatusDiscussion>,
+}
+
+#[derive(Deserialize)]
+struct StatusDiscussion {
+    url: String,
+    locked: bool,
+    closed: bool,
+}
+
+#[derive(Deserialize)]
+struct NodeData {
+    node: Option<CommentNode>,
+}
+
+#[derive(Deserialize)]
+struct CommentNode {
+    body: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AddCommentData {
+    #[serde(rename = "addDiscussionComment")]
+    add_discussion_comment: AddCommentPayload,
+}
+
+#[derive(Deserialize)]
+struct AddCommentPayload {
+    comment: DiscussionComment,
+}
+
+#[derive(Deserialize)]
+struct UpdateCommentData {
+    #[serde(rename = "updateDiscussionComment")]
+    update_discussion_comment: AddCommentPayload,
+}
@@ -1,47 +1,108 @@
-use std::{iter::once, sync::LazyLock, time::Duration};
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex},
+    time::Duration,
+};
 
 use anyhow::{Error, Result};
 use async_stream::try_stream;
-use chrono::{DateTime, Datelike, NaiveDateTime, Utc};
-use ego_tree::NodeRef;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Utc};
 use futures::Stream;
 use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
-use octocrab::{Octocrab, issues::IssueHandler, models::issues::Comment};
+use octocrab::{
+    Octocrab,
+    issues::IssueHandler,
+    models::{IssueState, issues::Comment},
+};
 use regex::Regex;
 use reqwest::Response;
-use scraper::Node;
-use scraper::{
-    ElementRef, Html,
-    Node::{Element, Text},
-    Selector,
-};
 
-use crate::args::EngineArgs;
+use crate::args::{EngineArgs, FinitePositiveF64, GitHubArgs};
+use crate::discussions::{Discussion, DiscussionComment};
 use crate::error::EngineCreationError;
-use crate::outcome::{Issue, Outcome};
+use crate::github::{Clients, GitHub};
+use crate::ledger::{Ledger, LedgerEntry, Trigger};
+use crate::marker::Marker;
+use crate::minutes::{ExtractionOptions, Mention, MinutesFormat, MinutesFormatKind};
+use crate::outcome::{Issue, IssueKind, Outcome};
+use crate::participants::Participants;
 use crate::repositories::Repository;
+use crate::runs::{CreatedComment, RunRecord, RunRecorder};
+use crate::template::{Placeholder, Template, Templates};
 
 /// The engine of this create, locating mentions to GitHub issues/PRs in minutes,
 /// and commenting the corresponding issue/PR with a link to the relevant part of the minutes.
 pub struct Engine {
     url: String,
-    dom: Html,
+    minutes: Box<dyn MinutesFormat>,
     repos: Vec<Repository>,
-    github: Octocrab,
+    github: Arc<GitHub>,
     min_date: DateTime<Utc>,
-    message_template: String,
-    transcript: bool,
-    governor: DefaultDirectRateLimiter,
+    template: Template,
+    groups: String,
+    date: NaiveDate,
+    channel: String,
+    run_id: String,
+    participants: Participants,
+    /// The label to add to issues once commented, if any
+    add_label: Option<String>,
+    /// The labels to remove from issues once commented
+    remove_labels: Vec<String>,
+    extraction: ExtractionOptions,
+    governor: Arc<DefaultDirectRateLimiter>,
+    /// Whether closed issues should be skipped
+    skip_closed: bool,
+    /// Whether each repository (`owner/repo`) is archived, as already fetched during this run
+    archived_repos: Mutex<HashMap<String, bool>>,
+    /// The login of the current account, once fetched
+    login: Mutex<Option<String>>,
+    /// Keeps track of the created comments, if a runs directory was provided
+    recorder: Option<RunRecorder>,
+    /// The ledger where the run is to be appended, if any
+    ledger: Option<Ledger>,
+    trigger: Trigger,
     dry_run: bool,
 }
 
+/// The GitHub client and the rate limiter of an engine,
+/// which can be shared by several engines (e.g. when processing a range of dates).
+#[derive(Clone)]
+pub struct Backend {
+    github: Arc<GitHub>,
+    governor: Arc<DefaultDirectRateLimiter>,
+}
+
+impl Backend {
+    pub fn new(github: Arc<GitHub>, rate_limit: FinitePositiveF64) -> Self {
+        Self {
+            github,
+            governor: Arc::new(RateLimiter::direct(
+                Quota::with_period(Duration::from_secs_f64(rate_limit.into())).unwrap(),
+            )),
+        }
+    }
+}
+
 impl Engine {
-    pub async fn new(token: String, args: EngineArgs) -> Result<Self, EngineCreationError> {
-        let channel_name = if args.channel.starts_with('#') {
-            &args.channel[1..]
-        } else {
-            &args.channel
+    pub async fn new(
+        github_args: GitHubArgs,
+        args: EngineArgs,
+    ) -> Result<Self, EngineCreationError> {
+        let github = Arc::new(GitHub::new(&github_args)?);
+        let backend = Backend::new(github, args.rate_limit);
+        Self::with_backend(github_args, args, backend).await
+    }
+
+    /// Create an engine using the given (possibly shared) backend, ignoring `args.rate_limit`.
+    pub async fn with_backend(
+        github_args: GitHubArgs,
+        args: EngineArgs,
+        backend: Backend,
+    ) -> Result<Self, EngineCreationError> {
+        let Some(channel) = args.channel.as_deref() else {
+            return Err(EngineCreationError::MissingChannel);
         };
+        let channel_name = channel.strip_prefix('#').unwrap_or(channel);
         let date = args.date.unwrap_or_else(||
             // infer date from URL if any, otherwise from current date
             args.url.as_ref()
@@ -56,7 +117,8 @@ impl Engine {
         log::debug!("Date: {date:?}");
         let url = args.url.unwrap_or_else(|| {
             format!(
-                "https://www.w3.org/{}/{:02}/{:02}-{}-minutes.html",
+                "{}/{}/{:02}/{:02}-{}-minutes.html",
+                args.minutes_url.trim_end_matches('/'),
                 date.year(),
                 date.month(),
                 date.day(),
@@ -65,9 +127,9 @@ impl Engine {
         });
         log::debug!("Minutes URL: {url:?}");
 
-        let html = if let Some(filename) = args.file {
+        let text = if let Some(filename) = &args.file {
             log::debug!("Reading from file {filename} instead of URL");
-            std::fs::read_to_string(&filename)?
+            std::fs::read_to_string(filename)?
         } else {
             reqwest::get(&url)
                 .await
@@ -77,13 +139,22 @@ impl Engine {
                 .await
                 .map_err(EngineCreationError::minutes)?
         };
-        let dom = Html::parse_document(&html);
+        let format = args.format.unwrap_or_else(|| {
+            MinutesFormatKind::guess(args.file.as_deref().unwrap_or(&url), &text)
+        });
+        log::debug!("Minutes format: {format:?}");
+        let minutes = format.parse(&text);
 
         let groups = args.groups.unwrap_or_else(|| format!("wg/{channel_name}"));
 
         let repos_urls: Vec<String> = groups
             .split(",")
-            .map(|g| format!("https://raw.githubusercontent.com/w3c/groups/refs/heads/main/{g}/repositories.json"))
+            .map(|g| {
+                format!(
+                    "{}/{g}/repositories.json",
+                    args.groups_url.trim_end_matches('/')
+                )
+            })
             .collect();
 
         let mut repos = vec![];
@@ -104,71 +175,266 @@ impl Engine {
                 .map(|x| Repository::from(x.as_str())),
         );
 
-        let github = Octocrab::builder().personal_token(token).build()?;
-        let min_date = NaiveDateTime::from(date.pred_opt().unwrap()).and_utc();
-        let message_template = format!(
-            "This was discussed during the [meeting of {} on {}](%URL%).",
-            groups,
-            date.format("%d %B %Y"),
-        );
+        let template = Templates::load(&args.templates)?
+            .for_groups(&groups)
+            .clone();
+        let participants = match &args.participants {
+            Some(path) => Participants::from_file(path)?,
+            None => Participants::default(),
+        };
+        if args.mention_participants && args.participants.is_none() {
+            log::warn!("No participants mapping provided, no participant will be mentioned");
+        }
+        let min_date =
+            NaiveDateTime::from(date - chrono::Days::new(args.look_back.into())).and_utc();
+        let run_id = Utc::now().format("%Y%m%dT%H%M%S%.3fZ").to_string();
+        log::debug!("Run id: {run_id}");
 
-        let governor = RateLimiter::direct(
-            Quota::with_period(Duration::from_secs_f64(args.rate_limit.into())).unwrap(),
-        );
+        let recorder = args.records.runs_dir.filter(|_| !args.dry_run).map(|dir| {
+            log::info!("Recording run {run_id} in {dir}");
+            RunRecorder::new(
+                dir,
+                RunRecord {
+                    run_id: run_id.clone(),
+                    channel: format!("#{channel_name}"),
+                    minutes: url.clone(),
+                    date,
+                    groups: groups.clone(),
+                    comments: vec![],
+                    reverted: false,
+                },
+            )
+        });
+
+        let extraction = ExtractionOptions {
+            transcript: args.transcript,
+            resolutions: args.resolutions,
+            participants: args.mention_participants,
+            default_repository: args.default_repository.as_deref().map(Repository::from),
+            heuristic: args.heuristic,
+            github_host: github_args.github_host,
+        };
+        for placeholder in template.missing(&extraction) {
+            let name = placeholder.name();
+            log::warn!(
+                "The comment template has no {{{name}}} placeholder, the requested {name} will never be included"
+            );
+        }
 
         Ok(Self {
             url,
-            dom,
+            minutes,
             repos,
-            github,
+            github: backend.github,
             min_date,
-            message_template,
-            transcript: args.transcript,
-            governor,
+            template,
+            channel: format!("#{channel_name}"),
+            groups,
+            date,
+            run_id,
+            participants,
+            add_label: args
+                .labels
+                .add_label
+                .map(|label| label.replace("{date}", &date.format("%Y-%m-%d").to_string())),
+            remove_labels: args.labels.remove_labels,
+            extraction,
+            governor: backend.governor,
+            skip_closed: args.skip_closed,
+            archived_repos: Mutex::default(),
+            login: Mutex::default(),
+            recorder,
+            ledger: args.records.ledger.map(Ledger::new),
+            trigger: Trigger::new(args.requested_by),
             dry_run: args.dry_run,
         })
     }
 
+    /// The date of the minutes processed by this engine.
+    pub fn date(&self) -> NaiveDate {
+        self.date
+    }
+
+    /// The URL of the minutes processed by this engine.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
     // Run the engine and yield a number of outcomes.
     pub fn run(&self) -> impl Stream<Item = Result<Outcome>> + '_ {
         try_stream! {
-            for (issue, link, fragment) in issues_with_link(&self.dom, &self.url, self.transcript) {
-                self.governor.until_ready().await;
-                log::debug!("{} referenced in {link}", issue.url);
+            // appended to the ledger when dropped, i.e. at the end of the run, or when the stream is dropped
+            let mut entry = self.ledger.as_ref().map(|ledger| ledger.start(self.ledger_entry()));
+            for await outcome in self.outcomes() {
+                let outcome = outcome?;
+                if let Some(entry) = &mut entry {
+                    entry.push(&outcome);
+                }
+                yield outcome;
+            }
+            if let Some(entry) = &mut entry {
+                entry.complete();
+            }
+        }
+    }
+
+    /// The entry of this run in the ledger, without any outcome yet.
+    fn ledger_entry(&self) -> LedgerEntry {
+        LedgerEntry {
+            run_id: self.run_id.clone(),
+            started: Utc::now(),
+            ended: Utc::now(),
+            trigger: self.trigger.clone(),
+            channel: self.channel.clone(),
+            date: self.date,
+            groups: self.groups.clone(),
+            minutes: self.url.clone(),
+            dry_run: self.dry_run,
+            completed: false,
+            outcomes: vec![],
+        }
+    }
 
-                if !self.repos.iter().any(|r| r.contains(&issue)) {
+    fn outcomes(&self) -> impl Stream<Item = Result<Outcome>> + '_ {
+        try_stream! {
+            let mentions = group_mentions(self.minutes.issues_with_link(&self.url, &self.extraction));
+            for mentions in &mentions {
+                let issue = mentions[0].issue.clone();
+                self.governor.until_ready().await;
+                for Mention { link, issue: i, .. } in mentions {
+                    log::debug!("{} referenced in {link} (as {})", issue.url, i.href);
+                }
+                // checked before any API call, as the GitHub App may not be installed for other repositories
+                if !self.owns(&issue) {
                     log::info!("Skipping {issue}, not owned by the current group(s)");
                     yield Outcome::not_owned(issue);
                     continue;
                 }
-                let issues = self.github.issues(issue.owner, issue.repo);
-                match comment_to_link(&link, &issues, issue.id, self.min_date).await {
+                let github = match self.github.clients(&issue.owner, &issue.repo).await {
+                    Err(err) => {
+                        log::error!("{:?}", err);
+                        yield Outcome::error(issue, Error::new(err).context("Authenticating"));
+                        continue;
+                    }
+                    Ok(github) => github,
+                };
+                let status = match self.status(&github, &issue).await {
+                    Err(err) => {
+                        log::error!("{:?}", err);
+                        yield Outcome::error(issue, err.context("Fetching issue"));
+                        continue;
+                    }
+                    Ok(None) => {
+                        log::warn!("Skipping {issue}, not found (deleted, or not visible with the current token)");
+                        yield Outcome::not_found(issue);
+                        continue;
+                    }
+                    Ok(Some(status)) => status,
+                };
+                let issue = issue.moved_to(status.current);
+                let github = match &issue.moved_from {
+                    None => github,
+                    Some(original) => {
+                        log::info!("{original} was moved to {}", issue.url);
+                        if !self.owns(&issue) {
+                            log::info!("Skipping {issue}, not owned by the current group(s)");
+                            yield Outcome::not_owned(issue);
+                            continue;
+                        }
+                        match self.github.clients(&issue.owner, &issue.repo).await {
+                            Err(err) => {
+                                log::error!("{:?}", err);
+                                yield Outcome::error(issue, Error::new(err).context("Authenticating"));
+                                continue;
+                            }
+                            Ok(github) => github,
+                        }
+                    }
+                };
+
+                if status.locked {
+                    log::warn!("Skipping {issue}, it is locked");
+                    yield Outcome::locked(issue);
+                    continue;
+                }
+                if status.closed && self.skip_closed {
+                    log::info!("Skipping {issue}, it is closed");
+                    yield Outcome::closed(issue);
+                    continue;
+                }
+                match self.archived(&github, &issue, status.archived).await {
+                    Err(err) => {
+                        log::error!("{:?}", err);
+                        yield Outcome::error(issue, err.context("Fetching repository"));
+                        continue;
+                    }
+                    Ok(true) => {
+                        log::warn!("Skipping {issue}, its repository is archived");
+                        yield Outcome::archived_repo(issue);
+                        continue;
+                    }
+                    Ok(false) => {}
+                }
+                if issue.kind == IssueKind::Discussion {
+                    yield self.comment_discussion(&github, issue, mentions).await;
+                    continue;
+                }
+                let (message, truncated) = self.message(mentions);
+                let issues = github.rest.issues(&issue.owner, &issue.repo);
+                let comments = match list_comments(&github.rest, &issues, issue.id, self.min_date).await {
                     Err(err) => {
                         log::error!("{:?}", err);
                         yield Outcome::error(issue, err.context("Fetching comments"));
                         continue;
                     }
-                    Ok(Some(comment)) => {
+                    Ok(comments) => comments,
+                };
+                let body = |c: &Comment| c.body.clone().unwrap_or_default();
+                match previous_comment(&comments, body, mentions, &self.marker(mentions), &message) {
+                    Previous::None => {}
+                    Previous::Duplicate(comment) => {
                         log::info!(
                             "Skipping {issue}, link to minutes already there: {}",
                             comment.html_url,
                         );
-                        yield Outcome::duplicate(issue, comment.html_url);
+                        yield Outcome::duplicate(issue, &comment.html_url);
+                        continue;
+                    }
+                    Previous::Stale(comment) => {
+                        match self.may_update(comment.html_url.as_str(), Some(&comment.user.login)).await {
+                            Err(err) => {
+                                log::error!("{:?}", err);
+                                yield Outcome::error(issue, err.context("Fetching current user"));
+                                continue;
+                            }
+                            Ok(false) => {
+                                yield Outcome::duplicate(issue, &comment.html_url);
+                                continue;
+                            }
+                            Ok(true) if self.dry_run => {
+                                log::info!("Comment updated: (not really, running in dry mode)");
+                                yield Outcome::faked(issue);
+                                continue;
+                            }
+                            Ok(true) => {}
+                        }
+                        match issues.update_comment(comment.id, &message).await {
+                            Err(err) => {
+                                log::error!("{:?}", err);
+                                yield Outcome::error(issue, Error::new(err).context("Updating comment"));
+                            }
+                            Ok(comment) if truncated => {
+                                log::info!("Comment updated (with truncated transcript): {}", comment.html_url);
+                                yield Outcome::truncated_update(issue, comment.html_url);
+                            }
+                            Ok(comment) => {
+                                log::info!("Comment updated: {}", comment.html_url);
+                                yield Outcome::updated(issue, comment.html_url);
+                            }
+                        }
                         continue;
                     }
-                    _ => {}
-                }
-
-                let mut message = self.message_template
-                    .replace("%URL%", &link);
-                if self.transcript {
-                    let transcript = format!(
-                        "\n\n<details><summary><i>View the transcript</i></summary>\n\n{}\n<hr /></details>",
-                        fragment,
-                    );
-                    message += &transcript;
                 }
-                log::trace!("Comment message: {message}");
 
                 if self.dry_run {
                     log::info!("Comment posted: (not really, running in dry mode)");
@@ -181,211 +447,709 @@ impl Engine {
                         yield Outcome::error(issue, Error::new(err).context("Posting comment"));
                         continue;
                     }
+                    Ok(comment) if truncated => {
+                        log::info!("Comment posted (with truncated transcript): {}", comment.html_url);
+                        self.record(&issue, comment.id.to_string(), comment.html_url.as_str());
+                        yield Outcome::truncated(issue.clone(), comment.html_url);
+                    }
                     Ok(comment) => {
                         log::info!("Comment posted: {}", comment.html_url);
-                        yield Outcome::created(issue, comment.html_url);
+                        self.record(&issue, comment.id.to_string(), comment.html_url.as_str());
+                        yield Outcome::created(issue.clone(), comment.html_url);
                     }
                 }
+                for outcome in self.update_labels(&github, &issue).await {
+                    yield outcome;
+                }
             }
         }
     }
-}
 
-/// Iter over all github issues cited in an HTML heading,
-/// together with the link to that heading,
-/// and optionally (see below) a markdown version of the part of the minutes where they are discussed.
-///
-/// The markdown fragment is only extracted if `transcript` is true,
-/// otherwise it will be an empty string.
-fn issues_with_link<'a>(
-    dom: &'a Html,
-    url: &'a str,
-    transcript: bool,
-) -> impl Iterator<Item = (Issue<'a>, String, String)> {
-    static SEL: LazyLock<Selector> = LazyLock::new(|| {
-        Selector::parse(r"h1[id] a, h2[id] a, h3[id] a, h4[id] a, h5[id] a, h6[id] a, h1[id] + * a, h2[id] + * a, h3[id] + * a, h4[id] + * a, h5[id] + * a, h6[id] + * a").unwrap()
-    });
-    dom.select(&SEL)
-        .map(|a| (a, a.attr("href").and_then(Issue::try_from_url)))
-        .filter_map(transpose_2nd)
-        .map(move |(a, issue)| (issue, find_relevant_hn_id(a, transcript)))
-        .filter_map(transpose_2nd)
-        .map(move |(issue, fragment)| {
-            (issue, format!("{}#{}", &url, fragment.id), fragment.content)
-        })
-}
+    /// Whether the stale comment at `url`, posted by `author`, may be updated,
+    /// i.e. whether it was posted by the current account.
+    async fn may_update(&self, url: &str, author: Option<&str>) -> Result<bool> {
+        let cached = self.login.lock().unwrap().clone();
+        let login = match cached {
+            Some(login) => login,
+            None => {
+                let login = self.github.login().await?;
+                *self.login.lock().unwrap() = Some(login.clone());
+                login
+            }
+        };
+        let mine = author.is_some_and(|author| same_login(author, &login));
+        if !mine {
+            log::info!(
+                "Not updating {url}, posted by {} rather than {login}",
+                author.unwrap_or("a deleted account"),
+            );
+        }
+        Ok(mine)
+    }
 
-/// Find a comment citing `url` in the given issue, if any.
-///
-/// NB: only issue posted after `min_date` are explored,
-/// and it is assumed that `min_date` is recent enough that no more than 200 comments are posted.
-async fn comment_to_link(
-    url: &str,
-    issues: &IssueHandler<'_>,
-    id: u64,
-    min_date: DateTime<Utc>,
-) -> Result<Option<Comment>> {
-    Ok(issues
-        .list_comments(id)
-        .since(min_date)
-        .per_page(200)
-        .send()
-        .await?
-        .items
-        .into_iter()
-        .find(|comment| {
-            comment
-                .body
-                .as_ref()
-                .filter(|txt| txt.contains(url))
-                .is_some()
+    /// Whether the given issue is in a repository owned by the current group(s).
+    fn owns(&self, issue: &Issue) -> bool {
+        self.repos.iter().any(|r| r.contains(issue))
+    }
+
+    /// The current status of the given issue, or `None` if it does not exist (anymore).
+    ///
+    /// The issue may have been transferred (or its repository renamed) since the minutes were written,
+    /// in which case the returned status points to its current location.
+    async fn status(&self, github: &Clients, issue: &Issue) -> Result<Option<Status>> {
+        if issue.kind == IssueKind::Discussion {
+            return self.discussion_status(github, issue).await;
+        }
+        let found = match github
+            .rest
+            .issues(&issue.owner, &issue.repo)
+            .get(issue.id)
+            .await
+        {
+            Err(octocrab::Error::GitHub { source, .. })
+                if source.status_code.as_u16() == 404 && issue.kind == IssueKind::Issue =>
+            {
+                // shorthands (e.g. `#123`) may refer to discussions, which the REST API ignores
+                log::debug!("{issue} not found, looking for a discussion");
+                return self.discussion_status(github, issue).await;
+            }
+            Err(octocrab::Error::GitHub { source, .. })
+                if matches!(source.status_code.as_u16(), 404 | 410) =>
+            {
+                return Ok(None);
+            }
+            found => found?,
+        };
+        Ok(Some(Status {
+            current: relocated(issue, found.html_url.as_str(), &self.extraction.github_host)?,
+            archived: None,
+            locked: found.locked,
+            closed: found.state == IssueState::Closed,
         }))
-}
+    }
 
-/// Transpose a tuple on its 2nd component.
-fn transpose_2nd<T, U>(pair: (T, Option<U>)) -> Option<(T, U)> {
-    match pair {
-        (_, None) => None,
-        (a, Some(b)) => Some((a, b)),
+    /// The current status of the given discussion, or `None` if it does not exist.
+    async fn discussion_status(&self, github: &Clients, issue: &Issue) -> Result<Option<Status>> {
+        let Some(status) =
+            Discussion::status(&github.graphql, &issue.owner, &issue.repo, issue.id).await?
+        else {
+            return Ok(None);
+        };
+        Ok(Some(Status {
+            current: relocated(issue, &status.url, &self.extraction.github_host)?,
+            archived: Some(status.archived),
+            locked: status.locked,
+            closed: status.closed,
+        }))
     }
-}
 
-#[expect(dead_code)]
-/// Find the header (h1, h2...) with an id which is the closest ancestor of the given element,
-/// and return the corresponding DocFragment.
-///
-/// Note that if content is false, the content field of the returned DocFragment will be an empty string.
-fn find_ancestor_hn_id(e: ElementRef, content: bool) -> Option<DocFragment> {
-    element_ancestors(e)
-        .filter_map(try_as_fragment_boundary)
-        .map(|(id, e)| {
-            if content {
-                extract_fragment(id, e)
-            } else {
-                DocFragment::dummy(id)
+    /// Whether the repository of the given issue is archived.
+    ///
+    /// Unless already `known`, this is fetched once per repository, and cached.
+    async fn archived(&self, github: &Clients, issue: &Issue, known: Option<bool>) -> Result<bool> {
+        if let Some(archived) = known {
+            return Ok(archived);
+        }
+        let key = format!("{}/{}", issue.owner, issue.repo);
+        if let Some(archived) = self.archived_repos.lock().unwrap().get(&key) {
+            return Ok(*archived);
+        }
+        let repo = github.rest.repos(&issue.owner, &issue.repo).get().await?;
+        let archived = repo.archived.unwrap_or(false);
+        self.archived_repos.lock().unwrap().insert(key, archived);
+        Ok(archived)
+    }
+
+    /// Record a comment created on `issue`, if runs are recorded.
+    ///
+    /// Failing to do so is logged, but does not interrupt the run.
+    fn record(&self, issue: &Issue, id: String, url: &str) {
+        let Some(recorder) = &self.recorder else {
+            return;
+        };
+        let comment = CreatedComment {
+            issue: issue.url.clone(),
+            id,
+            url: url.to_string(),
+        };
+        if let Err(err) = recorder.add(comment) {
+            log::error!("Could not record comment {url}: {:?}", Error::new(err));
+        }
+    }
+
+    /// The hidden marker added to the comment for the given `mentions` (of the same issue).
+    fn marker(&self, mentions: &[Mention]) -> Marker {
+        Marker {
+            minutes: self.url.clone(),
+            anchors: mentions
+                .iter()
+                .map(|m| anchor(&m.link).to_string())
+                .collect(),
+            date: self.date,
+            groups: self.groups.clone(),
+            version: Marker::VERSION.to_string(),
+        }
+    }
+
+    /// Build the comment message pointing to the sections of the `mentions` (of the same issue),
+    /// including their resolutions and transcripts if required.
+    ///
+    /// If the message exceeds [`MAX_COMMENT_LENGTH`], the transcripts are truncated at a paragraph boundary
+    /// (or dropped if the rest of the message is already too long), and the returned boolean is true.
+    fn message(&self, mentions: &[Mention]) -> (String, bool) {
+        let transcripts: Vec<_> = mentions.iter().map(|m| m.transcript.clone()).collect();
+        let message = self.render(mentions, &transcripts);
+        if message.chars().count() <= MAX_COMMENT_LENGTH {
+            log::trace!("Comment message: {message}");
+            return (message, false);
+        }
+        let continued = |m: &Mention| format!("\n\n*[…continued in the minutes]({})*", m.link);
+        let empty = vec![String::new(); mentions.len()];
+        let overhead = self.render(mentions, &empty).chars().count()
+            + mentions
+                .iter()
+                .map(|m| continued(m).chars().count())
+                .sum::<usize>();
+        if overhead > MAX_COMMENT_LENGTH {
+            log::warn!(
+                "Comment too long for {} even without transcript, transcript dropped",
+                mentions[0].issue
+            );
+            let message = self.render(mentions, &empty);
+            log::trace!("Comment message: {message}");
+            return (message, true);
+        }
+        let mut budget = MAX_COMMENT_LENGTH - overhead;
+        let transcripts: Vec<_> = mentions
+            .iter()
+            .map(|m| {
+                let length = m.transcript.chars().count();
+                if length <= budget {
+                    budget -= length;
+                    m.transcript.clone()
+                } else {
+                    let truncated = truncate_paragraphs(&m.transcript, budget);
+                    budget -= truncated.chars().count();
+                    truncated.to_string() + &continued(m)
+                }
+            })
+            .collect();
+        log::info!("Transcript truncated for {}", mentions[0].issue);
+        let message = self.render(mentions, &transcripts);
+        log::trace!("Comment message: {message}");
+        (message, true)
+    }
+
+    /// Render the template for the given `mentions` (of the same issue), with the given `transcripts`.
+    ///
+    /// `{url}` and `{topic}` refer to the first mention, while `{sections}` lists all of them.
+    fn render(&self, mentions: &[Mention], transcripts: &[String]) -> String {
+        let first = &mentions[0];
+        let resolutions = dedup(mentions.iter().flat_map(|m| &m.resolutions));
+        let speakers: Vec<_> = mentions.iter().flat_map(|m| &m.speakers).cloned().collect();
+        self.template.render(|placeholder| match placeholder {
+            Placeholder::Url => first.link.clone(),
+            Placeholder::Date => self.date.format("%d %B %Y").to_string(),
+            Placeholder::Groups => self.groups.clone(),
+            Placeholder::Topic => first.title.clone(),
+            Placeholder::Channel => self.channel.clone(),
+            Placeholder::RunId => self.run_id.clone(),
+            Placeholder::Sections if mentions.len() > 1 => {
+                let mut sections = "\n\nIt was discussed in the following sections:\n".to_string();
+                for m in mentions {
+                    let title = if m.title.is_empty() { &m.link } else { &m.title };
+                    sections += &format!("\n* [{title}]({})", m.link);
+                }
+                sections
             }
-        })
-        .next()
-}
+            Placeholder::Resolutions if !resolutions.is_empty() => {
+                let mut txt = "\n\nResolutions and action items:\n".to_string();
+                for resolution in &resolutions {
+                    txt += &format!("\n* {resolution}");
+                }
+                txt
+            }
+            Placeholder::Participants if !speakers.is_empty() => {
+                let logins = self.participants.logins(&speakers);
+                if logins.is_empty() {
+                    String::new()
+                } else {
+                    let logins: Vec<_> = logins.iter().map(|l| format!("@{l}")).collect();
+                    format!("\n\nParticipants: {}", logins.join(", "))
+                }
+            }
+            Placeholder::Transcript if self.extraction.transcript => mentions
+                .iter()
+                .zip(transcripts)
+                .map(|(m, transcript)| {
+                    let summary = if mentions.len() > 1 {
+                        format!("View the transcript of {}", m.title)
+                    } else {
+                        "View the transcript".to_string()
+                    };
+                    format!(
+                        "\n\n<details><summary><i>{summary}</i></summary>\n\n{transcript}\n<hr /></details>",
+                    )
+                })
+                .collect(),
+            Placeholder::Sections
+            | Placeholder::Resolutions
+            | Placeholder::Participants
+            | Placeholder::Transcript => String::new(),
+        }) + "\n\n"
+            + &self.marker(mentions).to_string()
+    }
 
-/// Find the header (h1, h2...) with an id which is the closest ancestor, or its predecessor, of the given element,
-/// and return the corresponding DocFragment.
-///
-/// Note that if content is false, the content field of the returned DocFragment will be an empty string.
-///
-/// This heuristics is somewhere in between [`find_ancestor_hn_id`] and [`find_closest_hn_id`].
-fn find_relevant_hn_id(e: ElementRef, content: bool) -> Option<DocFragment> {
-    element_ancestors(e)
-        .flat_map(element_prev_siblings)
-        .filter_map(try_as_fragment_boundary)
-        .map(|(id, e)| {
-            if content {
-                extract_fragment(id, e)
-            } else {
-                DocFragment::dummy(id)
+    /// Add and remove the configured labels to/from the given issue, once it has been commented.
+    ///
+    /// Labels that are to be removed but are not present on the issue are silently ignored.
+    async fn update_labels(&self, github: &Clients, issue: &Issue) -> Vec<Outcome> {
+        let issues = github.rest.issues(&issue.owner, &issue.repo);
+        let mut outcomes = vec![];
+        if let Some(label) = &self.add_label {
+            match issues
+                .add_labels(issue.id, std::slice::from_ref(label))
+                .await
+            {
+                Err(err) => {
+                    log::error!("{:?}", err);
+                    let err = Error::new(err).context(format!("Adding label {label}"));
+                    outcomes.push(Outcome::label_error(issue.clone(), label, err));
+                }
+                Ok(_) => {
+                    log::info!("Label {label} added to {issue}");
+                    outcomes.push(Outcome::labeled(issue.clone(), label));
+                }
             }
-        })
-        .next()
-}
+        }
+        for label in &self.remove_labels {
+            match issues.remove_label(issue.id, label).await {
+                Err(octocrab::Error::GitHub { source, .. })
+                    if source.status_code.as_u16() == 404 =>
+                {
+                    log::debug!("{issue} has no label {label}");
+                }
+                Err(err) => {
+                    log::error!("{:?}", err);
+                    let err = Error::new(err).context(format!("Removing label {label}"));
+                    outcomes.push(Outcome::label_error(issue.clone(), label, err));
+                }
+                Ok(_) => {
+                    log::info!("Label {label} removed from {issue}");
+                    outcomes.push(Outcome::unlabeled(issue.clone(), label));
+                }
+            }
+        }
+        outcomes
+    }
 
-#[expect(dead_code)]
-/// Find the header (h1, h2...) with an id which is closest before the given element,
-/// and return the corresponding DocFragment.
-///
-/// Note that if content is false, the content field of the returned DocFragment will be an empty string.
-fn find_closest_hn_id(e: ElementRef, content: bool) -> Option<DocFragment> {
-    element_ancestors(e)
-        .flat_map(element_prev_siblings)
-        .filter_map(try_as_fragment_boundary)
-        .map(|(id, e)| {
-            if content {
-                extract_fragment(id, e)
-            } else {
-                DocFragment::dummy(id)
+    /// Comment a GitHub discussion, which requires the GraphQL API.
+    async fn comment_discussion(
+        &self,
+        github: &Clients,
+        issue: Issue,
+        mentions: &[Mention],
+    ) -> Outcome {
+        let discussion =
+            match Discussion::fetch(&github.graphql, &issue.owner, &issue.repo, issue.id).await {
+                Err(err) => {
+                    log::error!("{:?}", err);
+                    return Outcome::error(issue, err.context("Fetching discussion"));
+                }
+                Ok(discussion) => discussion,
+            };
+        let (message, truncated) = self.message(mentions);
+        let body = |c: &DiscussionComment| c.body.clone();
+        match previous_comment(
+            &discussion.comments,
+            body,
+            mentions,
+            &self.marker(mentions),
+            &message,
+        ) {
+            Previous::None => {}
+            Previous::Duplicate(comment) => {
+                log::info!(
+                    "Skipping {issue}, link to minutes already there: {}",
+                    comment.url,
+                );
+                return Outcome::duplicate(issue, &comment.url);
             }
-        })
-        .next()
-}
+            Previous::Stale(comment) => {
+                let author = comment.author.as_ref().map(|a| a.login.as_str());
+                match self.may_update(&comment.url, author).await {
+                    Err(err) => {
+                        log::error!("{:?}", err);
+                        return Outcome::error(issue, err.context("Fetching current user"));
+                    }
+                    Ok(false) => return Outcome::duplicate(issue, &comment.url),
+                    Ok(true) if self.dry_run => {
+                        log::info!("Comment updated: (not really, running in dry mode)");
+                        return Outcome::faked(issue);
+                    }
+                    Ok(true) => {}
+                }
+                return match Discussion::update_comment(&github.graphql, &comment.id, &message)
+                    .await
+                {
+                    Err(err) => {
+                        log::error!("{:?}", err);
+                        Outcome::error(issue, err.context("Updating comment"))
+                    }
+                    Ok(comment) if truncated => {
+                        log::info!(
+                            "Comment updated (with truncated transcript): {}",
+                            comment.url
+                        );
+                        Outcome::truncated_update(issue, comment.url)
+                    }
+                    Ok(comment) => {
+                        log::info!("Comment updated: {}", comment.url);
+                        Outcome::updated(issue, comment.url)
+                    }
+                };
+            }
+        }
 
-/// If this element is a fragment boundary (i.e. a hn with and id),
-/// return its id and itself, otherwise, return None.
-fn try_as_fragment_boundary(e: ElementRef<'_>) -> Option<(&str, ElementRef<'_>)> {
-    static RE_HN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[hH][1234]$").unwrap());
-    let v = e.value();
-    if !RE_HN.is_match(v.name()) {
-        None
-    } else {
-        v.attr("id").map(|id| (id, e))
+        if self.dry_run {
+            log::info!("Comment posted: (not really, running in dry mode)");
+            return Outcome::faked(issue);
+        }
+        match discussion.add_comment(&github.graphql, &message).await {
+            Err(err) => {
+                log::error!("{:?}", err);
+                Outcome::error(issue, err.context("Posting comment"))
+            }
+            Ok(comment) if truncated => {
+                log::info!(
+                    "Comment posted (with truncated transcript): {}",
+                    comment.url
+                );
+                self.record(&issue, comment.id, &comment.url);
+                Outcome::truncated(issue, comment.url)
+            }
+            Ok(comment) => {
+                log::info!("Comment posted: {}", comment.url);
+                self.record(&issue, comment.id, &comment.url);
+                Outcome::created(issue, comment.url)
+            }
+        }
     }
 }
 
-/// Extract and convert to markdown the fragment reachable from this element.
-///
-/// Note that the markdown is in fact sanitized HTML (which is compatible with markdown).
-/// Note also `@words` are surrounded with `<code>` to prevent spurious @-mentions of github users.
+/// The current status of an issue, as returned by [`Engine::status`].
+struct Status {
+    /// The current location of the issue
+    current: Issue,
+    /// Whether the repository of the issue is archived, if known
+    /// (it is not provided by the REST API of issues)
+    archived: Option<bool>,
+    locked: bool,
+    closed: bool,
+}
+
+/// The issue located at `url`, which is the current location of `issue`.
+fn relocated(issue: &Issue, url: &str, host: &str) -> Result<Issue> {
+    Issue::try_from_url(url, host)
+        .ok_or_else(|| Error::msg(format!("Unexpected URL for {issue}: {url}")))
+}
+
+/// A comment previously posted for a given issue, as found by [`previous_comment`].
+enum Previous<'c, C> {
+    /// No such comment
+    None,
+    /// A comment that does not need to be updated
+    Duplicate(&'c C),
+    /// A comment that needs to be updated
+    Stale(&'c C),
+}
+
+/// Find, among `comments`, the one previously posted for the given `mentions` (of the same issue), if any.
 ///
-/// # Precondition
-/// Element `e` must have an `id` attribute.
-fn extract_fragment<'a>(id: &'a str, e: ElementRef<'a>) -> DocFragment<'a> {
-    static AT_WORD: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"@[A-Za-z0-9_]+").unwrap());
-    debug_assert!(e.value().attr("id") == Some(id));
-    let e_frag = e.html();
-    let s_frags = e
-        .next_siblings()
-        .take_while(not_fragment_boundary)
-        .filter_map(|n| match n.value() {
-            Text(txt) => Some(txt.to_string()),
-            Element(_) => ElementRef::wrap(n).map(|er| er.html()),
-            _ => None,
+/// This is either a comment whose [`Marker`] points to the same minutes as `marker`
+/// (even if the anchors differ, as they may change when the minutes are regenerated),
+/// or a comment without marker citing one of the mentions (posted by an older version of this program).
+/// A comment bearing a marker is considered stale if its content differs from `message`
+/// (see [`comparable`]).
+fn previous_comment<'c, C>(
+    comments: &'c [C],
+    body: impl Fn(&C) -> String,
+    mentions: &[Mention],
+    marker: &Marker,
+    message: &str,
+) -> Previous<'c, C> {
+    let with_marker: Vec<_> = comments
+        .iter()
+        .map(|c| {
+            let body = body(c);
+            let marker = Marker::find(&body);
+            (c, body, marker)
+        })
+        .collect();
+    let found = with_marker
+        .iter()
+        .find(|(_, _, m)| m.as_ref().is_some_and(|m| m.minutes == marker.minutes))
+        .or_else(|| {
+            with_marker.iter().find(|(_, b, m)| {
+                m.is_none() && mentions.iter().any(|mention| b.contains(&mention.link))
+            })
         });
-    let html = once(e_frag).chain(s_frags).collect::<Vec<_>>().join("");
-    let content = ammonia::clean(&html);
-    let content = AT_WORD.replace_all(&content, "<code>$0</code>").to_string();
-    DocFragment { id, content }
+    match found {
+        None => Previous::None,
+        Some((c, b, Some(_))) if comparable(b) != comparable(message) => Previous::Stale(c),
+        Some((c, _, _)) => Previous::Duplicate(c),
+    }
 }
 
-fn not_fragment_boundary(n: &NodeRef<Node>) -> bool {
-    let Some(e) = ElementRef::wrap(*n) else {
-        return true;
-    };
-    try_as_fragment_boundary(e).is_none()
+/// The content of a comment, ignoring what changes from one run to another without changing its meaning:
+/// run ids, the version of this program in the marker, and line endings.
+fn comparable(body: &str) -> String {
+    static RUN_ID: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"[0-9]{8}T[0-9]{6}\.[0-9]{3}Z").unwrap());
+    static VERSION: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(<!-- minutes_to_gh [^\n]*)version=\S* -->").unwrap());
+    let body = body.replace("\r\n", "\n");
+    let body = RUN_ID.replace_all(&body, "");
+    VERSION.replace_all(&body, "$1-->").trim().to_string()
 }
 
-/// Iterate over all ancestors of e that are elements.
-///
-/// "ancestors" here is to be understood in the broad sense:
-/// e is yielded as its first ancestor.
-fn element_ancestors(e: ElementRef) -> impl Iterator<Item = ElementRef> {
-    once(e).chain(e.ancestors().filter_map(ElementRef::wrap))
+/// Whether two logins designate the same account
+/// (the login of a bot is suffixed with `[bot]` in the REST API, but not in the GraphQL API).
+fn same_login(a: &str, b: &str) -> bool {
+    a.trim_end_matches("[bot]")
+        .eq_ignore_ascii_case(b.trim_end_matches("[bot]"))
 }
 
-/// Iterate over all previous siblings of e that are elements.
-///
-/// "sibling" here is to be understood in the broad sense:
-/// e is yielded as its first sibling.
-fn element_prev_siblings(e: ElementRef) -> impl Iterator<Item = ElementRef> {
-    once(e).chain(e.prev_siblings().filter_map(ElementRef::wrap))
+/// Group mentions by issue (in order of first appearance),
+/// ignoring multiple mentions of an issue in the same section.
+fn group_mentions(mentions: impl IntoIterator<Item = Mention>) -> Vec<Vec<Mention>> {
+    let mut groups: Vec<Vec<Mention>> = vec![];
+    let mut index: HashMap<Issue, usize> = HashMap::new();
+    for mention in mentions {
+        match index.get(&mention.issue) {
+            Some(&i) if groups[i].iter().any(|m| m.link == mention.link) => {}
+            Some(&i) => groups[i].push(mention),
+            None => {
+                index.insert(mention.issue.clone(), groups.len());
+                groups.push(vec![mention]);
+            }
+        }
+    }
+    groups
 }
 
-/// Combines an ID from a DOM tree with the markdown version of the fragment "accessible" from this ID.
-struct DocFragment<'a> {
-    id: &'a str,
-    content: String,
+/// Remove duplicates from `items`, keeping their order of appearance.
+fn dedup<'a>(items: impl IntoIterator<Item = &'a String>) -> Vec<&'a String> {
+    let mut ret: Vec<&String> = vec![];
+    for item in items {
+        if !ret.contains(&item) {
+            ret.push(item);
+        }
+    }
+    ret
 }
 
-impl<'a> DocFragment<'a> {
-    fn dummy(id: &'a str) -> Self {
-        Self {
-            id,
-            content: "".into(),
+/// The anchor of a link to a section of the minutes.
+fn anchor(link: &str) -> &str {
+    link.rsplit_once('#').map_or("", |(_, anchor)| anchor)
+}
+
+/// List the comments of the given issue posted after `min_date`, going through all pages of results.
+async fn list_comments(
+    github: &Octocrab,
+    issues: &IssueHandler<'_>,
+    id: u64,
+    min_date: DateTime<Utc>,
+) -> Result<Vec<Comment>> {
+    let first_page = issues
+        .list_comments(id)
+        .since(min_date)
+        .per_page(100)
+        .send()
+        .await?;
+    Ok(github.all_pages(first_page).await?)
+}
+
+/// The maximum length (in characters) of a comment body accepted by GitHub.
+const MAX_COMMENT_LENGTH: usize = 65536;
+
+/// The longest prefix of `txt` made of whole paragraphs and no longer than `max` characters.
+///
+/// Blank lines inside fenced code blocks are not considered as paragraph boundaries,
+/// so that the prefix never ends with an unclosed fence.
+fn truncate_paragraphs(txt: &str, max: usize) -> &str {
+    if txt.chars().count() <= max {
+        return txt;
+    }
+    let (mut end, mut offset, mut chars) = (0, 0, 0);
+    let mut fence: Option<&str> = None;
+    for line in txt.split_inclusive('\n') {
+        if offset > 0 && chars - 1 > max {
+            break;
+        }
+        let trimmed = line.trim_start();
+        match fence {
+            None if line == "\n" && offset > 0 => end = offset - 1,
+            None if trimmed.starts_with("```") || trimmed.starts_with("~~~") => {
+                fence = Some(&trimmed[..3]);
+            }
+            Some(f) if trimmed.starts_with(f) => fence = None,
+            _ => {}
         }
+        offset += line.len();
+        chars += line.chars().count();
     }
+    &txt[..end]
 }
 
-fn today() -> chrono::NaiveDate {
+pub fn today() -> chrono::NaiveDate {
     chrono::offset::Local::now().date_naive()
 }
 
 static DATE_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"[^0-9]([0-9]{4})[\/-]([0-9]{2})[\/-]([0-9]{2})(?:$|[^0-9])").unwrap()
 });
+
+#[cfg(test)]
+mod e2e;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::outcome::GITHUB_HOST;
+    use test_case::test_case;
+
+    fn mention(id: u64, anchor: &str) -> Mention {
+        Mention {
+            issue: Issue::new(GITHUB_HOST, "w3c", "vc", id),
+            link: format!("https://example.org/minutes.html#{anchor}"),
+            title: format!("Section {anchor}"),
+            transcript: String::new(),
+            resolutions: vec![],
+            speakers: vec![],
+        }
+    }
+
+    fn marker(anchors: &[&str]) -> Marker {
+        Marker {
+            minutes: "https://example.org/minutes.html".into(),
+            anchors: anchors.iter().map(ToString::to_string).collect(),
+            date: NaiveDate::from_ymd_opt(2024, 11, 14).unwrap(),
+            groups: "wg/vc".into(),
+            version: Marker::VERSION.into(),
+        }
+    }
+
+    fn previous(comments: &[&str], message: &str) -> String {
+        let comments: Vec<String> = comments.iter().map(ToString::to_string).collect();
+        let mentions = [mention(1, "t04"), mention(1, "t01")];
+        match super::previous_comment(
+            &comments,
+            Clone::clone,
+            &mentions,
+            &marker(&["t04", "t01"]),
+            message,
+        ) {
+            Previous::None => "none".into(),
+            Previous::Duplicate(c) => format!("duplicate {c}"),
+            Previous::Stale(c) => format!("stale {c}"),
+        }
+    }
+
+    #[test]
+    fn previous_comment() {
+        let link = "https://example.org/minutes.html";
+        let message = format!("see {link}#t04\n\n{}", marker(&["t04", "t01"]));
+        let legacy = format!("see {link}#t01");
+        let stale = format!("see {link}#t03\n\n{}", marker(&["t03"]));
+        let other = format!(
+            "see https://example.org/other.html#t04\n\n{}",
+            Marker {
+                minutes: "https://example.org/other.html".into(),
+                ..marker(&["t04"])
+            }
+        );
+        assert_eq!(previous(&[], &message), "none");
+        assert_eq!(previous(&["unrelated"], &message), "none");
+        assert_eq!(previous(&[&other], &message), "none");
+        assert_eq!(
+            previous(&[&message], &message),
+            format!("duplicate {message}")
+        );
+        assert_eq!(
+            previous(&[&legacy], &message),
+            format!("duplicate {legacy}")
+        );
+        assert_eq!(
+            previous(&[&other, &stale], &message),
+            format!("stale {stale}")
+        );
+        let upgraded = message.replace(&format!("version={}", Marker::VERSION), "version=0.0.1");
+        assert_eq!(
+            previous(&[&upgraded], &message),
+            format!("duplicate {upgraded}")
+        );
+    }
+
+    #[test_case("see run 20241114T160512.123Z", "see run 20241115T090000.000Z" => true)]
+    #[test_case("line 1\r\nline 2\n", "line 1\nline 2" => true)]
+    #[test_case("<!-- minutes_to_gh minutes=a version=0.9.0 -->", "<!-- minutes_to_gh minutes=a version=0.10.0 -->" => true)]
+    #[test_case("<!-- minutes_to_gh minutes=a version=0.9.0 -->", "<!-- minutes_to_gh minutes=b version=0.9.0 -->" => false)]
+    fn comparable(a: &str, b: &str) -> bool {
+        super::comparable(a) == super::comparable(b)
+    }
+
+    #[test_case("m2g", "M2G" => true)]
+    #[test_case("m2g-app[bot]", "m2g-app" => true)]
+    #[test_case("m2g", "someone" => false)]
+    fn same_login(a: &str, b: &str) -> bool {
+        super::same_login(a, b)
+    }
+
+    #[test]
+    fn group_mentions() {
+        let mentions = [
+            mention(1, "t01"),
+            mention(2, "t01"),
+            mention(1, "t01"),
+            mention(1, "t03"),
+        ];
+        let got: Vec<Vec<_>> = super::group_mentions(mentions)
+            .into_iter()
+            .map(|g| {
+                g.into_iter()
+                    .map(|m| (m.issue.id, anchor(&m.link).to_string()))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                vec![(1, "t01".to_string()), (1, "t03".to_string())],
+                vec![(2, "t01".to_string())],
+            ]
+        );
+    }
+
+    #[test_case("one\n\ntwo\n\nthree", 100 => "one\n\ntwo\n\nthree")]
+    #[test_case("one\n\ntwo\n\nthree", 8 => "one\n\ntwo")]
+    #[test_case("one\n\ntwo\n\nthree", 7 => "one")]
+    #[test_case("one\n\ntwo\n\nthree", 2 => "")]
+    #[test_case("é\n\nà", 1 => "é")]
+    #[test_case("one\n\n```\ntwo\n\nthree\n```\n\nfour", 20 => "one")]
+    #[test_case("one\n\n```\ntwo\n\nthree\n```\n\nfour", 25 => "one\n\n```\ntwo\n\nthree\n```")]
+    #[test_case("one\n\n~~~\n```\n\n```\n~~~\n\nfour", 21 => "one\n\n~~~\n```\n\n```\n~~~")]
+    fn truncate_paragraphs(txt: &str, max: usize) -> &str {
+        super::truncate_paragraphs(txt, max)
+    }
+
+    #[test]
+    fn truncate_long_pre() {
+        // as rendered from a long <pre> element in HTML minutes
+        let code = "some code\n\n".repeat(1000);
+        let transcript = format!("Before\n\n```\n{}\n```\n\nAfter", code.trim_end());
+        assert_eq!(super::truncate_paragraphs(&transcript, 5000), "Before");
+        let whole = super::truncate_paragraphs(&transcript, transcript.chars().count() - 1);
+        assert!(whole.ends_with("some code\n```"));
+    }
+}
@@ -0,0 +1,580 @@
+//! End-to-end tests of [`Engine::run`], against a mock server standing for GitHub and w3c/groups.
+
+use clap::Parser;
+use futures::TryStreamExt;
+use serde_json::{Value, json};
+use test_case::test_case;
+use wiremock::matchers::{body_partial_json, body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use super::*;
+use crate::args::{CmdArgs, GitHubArgs, ManualArgs, SubCmdArgs};
+use crate::outcome::OutcomeKind;
+
+const MINUTES: &str = r#"<!DOCTYPE html>
+<html><body>
+<section>
+  <h3 id="t01">1. <a href="https://github.com/w3c/vc/issues/1">Issue 1</a></h3>
+  <p>We discussed it.</p>
+</section>
+<section>
+  <h3 id="t02">2. Already linked (w3c/vc#2)</h3>
+</section>
+<section>
+  <h3 id="t03">3. Someone else's problem (other/repo#3)</h3>
+</section>
+<section>
+  <h3 id="t04">4. Failing (w3c/vc#4)</h3>
+</section>
+</body></html>
+"#;
+
+fn user() -> Value {
+    let url = "https://api.github.com/users/m2g";
+    json!({
+        "login": "m2g", "id": 1, "node_id": "U1", "type": "User", "site_admin": false,
+        "avatar_url": url, "gravatar_id": "", "url": url, "html_url": url,
+        "followers_url": url, "following_url": url, "gists_url": url, "starred_url": url,
+        "subscriptions_url": url, "organizations_url": url, "repos_url": url,
+        "events_url": url, "received_events_url": url, "patch_url": null,
+    })
+}
+
+fn issue(repo: &str, number: u64) -> Value {
+    let url = format!("https://api.github.com/repos/{repo}/issues/{number}");
+    json!({
+        "id": number, "node_id": format!("I{number}"), "number": number,
+        "url": url, "repository_url": url, "labels_url": url, "comments_url": url, "events_url": url,
+        "html_url": format!("https://github.com/{repo}/issues/{number}"),
+        "state": "open", "state_reason": null, "title": "An issue", "body": null,
+        "user": user(), "labels": [], "assignees": [], "author_association": "NONE",
+        "locked": false, "comments": 0,
+        "created_at": "2024-11-01T00:00:00Z", "updated_at": "2024-11-01T00:00:00Z",
+    })
+}
+
+fn repository(repo: &str) -> Value {
+    let (_, name) = repo.split_once('/').unwrap();
+    json!({ "id": 1, "name": name, "url": format!("https://api.github.com/repos/{repo}"), "archived": false })
+}
+
+fn comment(repo: &str, number: u64, id: u64, body: &str) -> Value {
+    let url = format!("https://api.github.com/repos/{repo}/issues/comments/{id}");
+    json!({
+        "id": id, "node_id": format!("C{id}"), "url": url,
+        "html_url": format!("https://github.com/{repo}/issues/{number}#issuecomment-{id}"),
+        "body": body, "author_association": "NONE", "user": user(),
+        "created_at": "2024-11-14T00:00:00Z",
+    })
+}
+
+/// Mount the mocks for an existing issue (and its repository) with the given comments.
+async fn mock_issue(server: &MockServer, repo: &str, number: u64, comments: Vec<Value>) {
+    Mock::given(method("GET"))
+        .and(path(format!("/repos/{repo}/issues/{number}")))
+        .respond_with(ResponseTemplate::new(200).set_body_json(issue(repo, number)))
+        .mount(server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(format!("/repos/{repo}")))
+        .respond_with(ResponseTemplate::new(200).set_body_json(repository(repo)))
+        .mount(server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(format!("/repos/{repo}/issues/{number}/comments")))
+        .respond_with(ResponseTemplate::new(200).set_body_json(comments))
+        .mount(server)
+        .await;
+}
+
+/// A mock server serving the minutes, the repositories of wg/vc, and the GitHub API.
+async fn mock_server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/2024/11/14-vc-minutes.html"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(MINUTES))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/groups/wg/vc/repositories.json"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!([{ "name": "vc", "owner": { "login": "w3c" } }])),
+        )
+        .mount(&server)
+        .await;
+
+    mock_issue(&server, "w3c/vc", 1, vec![]).await;
+    Mock::given(method("POST"))
+        .and(path("/repos/w3c/vc/issues/1/comments"))
+        .respond_with(ResponseTemplate::new(201).set_body_json(comment("w3c/vc", 1, 11, "")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let link = format!("{}/2024/11/14-vc-minutes.html#t02", server.uri());
+    let previous = comment("w3c/vc", 2, 21, &format!("See {link}"));
+    mock_issue(&server, "w3c/vc", 2, vec![previous]).await;
+
+    mock_issue(&server, "other/repo", 3, vec![]).await;
+
+    mock_issue(&server, "w3c/vc", 4, vec![]).await;
+    Mock::given(method("POST"))
+        .and(path("/repos/w3c/vc/issues/4/comments"))
+        .respond_with(
+            ResponseTemplate::new(422).set_body_json(json!({ "message": "Validation Failed" })),
+        )
+        .mount(&server)
+        .await;
+
+    server
+}
+
+/// The arguments of manual mode against `server`, with the given extra arguments.
+fn manual_args(server: &MockServer, extra_args: &[&str]) -> (GitHubArgs, ManualArgs) {
+    let uri = server.uri();
+    let groups_url = format!("{uri}/groups");
+    let args = [
+        "minutes_to_gh",
+        "--token",
+        "secret",
+        "--github-api-url",
+        &uri,
+        "manual",
+        "--channel",
+        "vc",
+        "--groups-url",
+        &groups_url,
+        "--rate-limit",
+        "0.001",
+    ];
+    let cmd = CmdArgs::parse_from(args.iter().chain(extra_args));
+    let SubCmdArgs::Manual(args) = cmd.subcommand else {
+        unreachable!()
+    };
+    (cmd.github, args)
+}
+
+/// Run the engine against `server` in manual mode, with the given extra arguments.
+async fn run(server: &MockServer, extra_args: &[&str]) -> Vec<String> {
+    let minutes = format!("{}/2024/11/14-vc-minutes.html", server.uri());
+    let extra_args: Vec<&str> = ["--url", &minutes]
+        .into_iter()
+        .chain(extra_args.iter().copied())
+        .collect();
+    let (github, args) = manual_args(server, &extra_args);
+    let engine = Engine::new(github, args.engine).await.unwrap();
+    let outcomes: Vec<Outcome> = engine.run().try_collect().await.unwrap();
+    outcomes.iter().map(describe).collect()
+}
+
+/// A short description of the given outcome, for comparison.
+fn describe(o: &Outcome) -> String {
+    match &o.kind {
+        OutcomeKind::Created(comment) => format!("created {comment}"),
+        OutcomeKind::Duplicate(comment) => format!("duplicate {comment}"),
+        OutcomeKind::NotOwned => format!("not owned {}", o.issue),
+        OutcomeKind::Faked => format!("faked {}", o.issue),
+        OutcomeKind::Updated(comment) => format!("updated {comment}"),
+        OutcomeKind::LabelError(label, _) => format!("label error {label} {}", o.issue),
+        OutcomeKind::Error(_) => format!("error {}", o.issue),
+        other => format!("unexpected {other:?} {}", o.issue),
+    }
+}
+
+/// The bodies of the comments posted on the mock server.
+async fn posted(server: &MockServer) -> Vec<String> {
+    server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|r| r.method.as_str() == "POST")
+        .map(|r| {
+            r.body_json::<Value>().unwrap()["body"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn outcomes() {
+    let server = mock_server().await;
+    let got = run(&server, &[]).await;
+    assert_eq!(
+        got,
+        vec![
+            "created https://github.com/w3c/vc/issues/1#issuecomment-11",
+            "duplicate https://github.com/w3c/vc/issues/2#issuecomment-21",
+            "not owned https://github.com/other/repo/issues/3",
+            "error https://github.com/w3c/vc/issues/4",
+        ]
+    );
+    let posted = posted(&server).await;
+    assert_eq!(posted.len(), 2); // for #1, and the failed attempt for #4
+    let expected_link = format!("{}/2024/11/14-vc-minutes.html#t01", server.uri());
+    assert!(posted[0].contains(&expected_link));
+    assert!(posted[0].contains("<!-- minutes_to_gh "));
+    // issues not owned by the group are skipped without calling the API
+    let requests = server.received_requests().await.unwrap();
+    assert!(
+        requests
+            .iter()
+            .all(|r| !r.url.path().starts_with("/repos/other/"))
+    );
+    // the repository is fetched only once, for all its issues
+    let repo_gets = requests.iter().filter(|r| r.url.path() == "/repos/w3c/vc");
+    assert_eq!(repo_gets.count(), 1);
+}
+
+#[tokio::test]
+async fn dry_run() {
+    let server = mock_server().await;
+    let got = run(&server, &["--dry-run"]).await;
+    assert_eq!(
+        got,
+        vec![
+            "faked https://github.com/w3c/vc/issues/1",
+            "duplicate https://github.com/w3c/vc/issues/2#issuecomment-21",
+            "not owned https://github.com/other/repo/issues/3",
+            "faked https://github.com/w3c/vc/issues/4",
+        ]
+    );
+    assert!(posted(&server).await.is_empty());
+    // the expectations of the server (one comment posted for #1) are not met on purpose
+    server.reset().await;
+}
+
+#[tokio::test]
+async fn ledger() {
+    let server = mock_server().await;
+    let path = std::env::temp_dir().join(format!("m2g-e2e-ledger-{}.jsonl", std::process::id()));
+    let path = path.to_str().unwrap();
+    run(&server, &["--ledger", path]).await;
+
+    let content = std::fs::read_to_string(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    let entry: crate::ledger::LedgerEntry = serde_json::from_str(content.trim()).unwrap();
+    assert!(entry.completed);
+    assert_eq!(entry.trigger, crate::ledger::Trigger::Cli);
+    assert_eq!(entry.channel, "#vc");
+    let kinds: Vec<_> = entry.outcomes.iter().map(|o| o.kind.as_str()).collect();
+    assert_eq!(kinds, vec!["created", "duplicate", "not_owned", "error"]);
+    assert_eq!(
+        entry.outcomes[0].comment.as_deref(),
+        Some("https://github.com/w3c/vc/issues/1#issuecomment-11")
+    );
+    assert_eq!(entry.outcomes[3].error[0], "Posting comment");
+}
+
+#[tokio::test]
+async fn manifest() {
+    use crate::manual::{ManifestEntry, Report, batch};
+
+    let server = mock_server().await;
+    let uri = server.uri();
+    let (github, mut args) = manual_args(&server, &[]);
+    args.engine.channel = None;
+    let manifest = vec![
+        ManifestEntry {
+            url: Some(format!("{uri}/2024/11/14-vc-minutes.html")),
+            channel: Some("vc".into()),
+            groups: Some("wg/vc".into()),
+            ..Default::default()
+        },
+        ManifestEntry {
+            url: Some(format!("{uri}/2024/11/15-vc-minutes.html")),
+            channel: Some("vc".into()),
+            ..Default::default()
+        },
+        ManifestEntry {
+            url: Some(format!("{uri}/2024/11/14-vc-minutes.html")),
+            ..Default::default()
+        },
+    ];
+    let reports: Vec<Report> = batch(github, args.engine, manifest)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(reports.len(), 3);
+    let Report::Processed(meeting) = &reports[0] else {
+        panic!("first entry not processed");
+    };
+    assert_eq!(meeting.date.to_string(), "2024-11-14");
+    let got: Vec<String> = meeting.outcomes.iter().map(describe).collect();
+    assert_eq!(
+        got,
+        vec![
+            "created https://github.com/w3c/vc/issues/1#issuecomment-11",
+            "duplicate https://github.com/w3c/vc/issues/2#issuecomment-21",
+            "not owned https://github.com/other/repo/issues/3",
+            "error https://github.com/w3c/vc/issues/4",
+        ]
+    );
+    let Report::Failed(source, _) = &reports[1] else {
+        panic!("second entry not failed");
+    };
+    assert_eq!(source, &format!("{uri}/2024/11/15-vc-minutes.html"));
+    let Report::Failed(_, err) = &reports[2] else {
+        panic!("entry without channel not failed");
+    };
+    assert_eq!(err.to_string(), "No IRC channel given");
+}
+
+#[tokio::test]
+async fn backfill() {
+    use crate::manual::{Report, backfill};
+
+    let server = mock_server().await;
+    Mock::given(method("GET"))
+        .and(path("/2024/11/15-vc-minutes.html"))
+        .respond_with(ResponseTemplate::new(403))
+        .mount(&server)
+        .await;
+    let uri = server.uri();
+    let (github, args) = manual_args(&server, &["--minutes-url", &uri]);
+    let from = chrono::NaiveDate::from_ymd_opt(2024, 11, 13).unwrap();
+    let to = chrono::NaiveDate::from_ymd_opt(2024, 11, 16).unwrap();
+    let reports: Vec<String> = backfill(github, args.engine, from, to)
+        .map_ok(|report| match report {
+            Report::Processed(meeting) => format!("processed {}", meeting.summary()),
+            Report::Failed(source, _) => format!("failed {source}"),
+        })
+        .try_collect()
+        .await
+        .unwrap();
+    // no minutes on the 13th and 16th (404)
+    assert_eq!(
+        reports,
+        vec![
+            format!(
+                "processed 2024-11-14 {uri}/2024/11/14-vc-minutes.html: 1 created, 1 duplicate, 1 not owned, 1 error"
+            ),
+            "failed 2024-11-15".to_string(),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn label_error() {
+    let server = mock_server().await;
+    Mock::given(method("POST"))
+        .and(path("/repos/w3c/vc/issues/1/labels"))
+        .respond_with(
+            ResponseTemplate::new(422).set_body_json(json!({ "message": "Validation Failed" })),
+        )
+        .mount(&server)
+        .await;
+
+    let got = run(&server, &["--add-label", "discussed-{date}"]).await;
+    assert_eq!(
+        got[..2],
+        [
+            "created https://github.com/w3c/vc/issues/1#issuecomment-11",
+            "label error discussed-2024-11-14 https://github.com/w3c/vc/issues/1",
+        ]
+    );
+}
+
+const DISCUSSION_MINUTES: &str = r#"<!DOCTYPE html>
+<html><body>
+<section>
+  <h3 id="t01">1. <a href="https://github.com/w3c/vc/discussions/5">New discussion</a></h3>
+</section>
+<section>
+  <h3 id="t02">2. <a href="https://github.com/w3c/vc/discussions/6">Stale discussion</a></h3>
+</section>
+<section>
+  <h3 id="t03">3. <a href="https://github.com/w3c/vc/discussions/7">Linked discussion</a></h3>
+</section>
+<section>
+  <h3 id="t04">4. Shorthand for a discussion (#8)</h3>
+</section>
+</body></html>
+"#;
+
+fn discussion_comment(number: u64, id: u64, body: &str) -> Value {
+    json!({
+        "id": format!("DC{id}"),
+        "url": format!("https://github.com/w3c/vc/discussions/{number}#discussioncomment-{id}"),
+        "body": body,
+        "author": { "login": "m2g" },
+    })
+}
+
+/// Mount the GraphQL mocks for an existing discussion of w3c/vc with the given comments.
+async fn mock_discussion(server: &MockServer, number: u64, comments: Vec<Value>) {
+    let status = json!({ "data": { "repository": { "isArchived": false, "discussion": {
+        "url": format!("https://github.com/w3c/vc/discussions/{number}"),
+        "locked": false, "closed": false,
+    } } } });
+    Mock::given(method("POST"))
+        .and(path("/graphql"))
+        .and(body_string_contains("isArchived"))
+        .and(body_partial_json(
+            json!({ "variables": { "number": number } }),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(status))
+        .mount(server)
+        .await;
+    let discussion = json!({ "data": { "repository": { "discussion": {
+        "id": format!("D{number}"),
+        "comments": {
+            "pageInfo": { "hasNextPage": false, "endCursor": null },
+            "nodes": comments,
+        },
+    } } } });
+    Mock::given(method("POST"))
+        .and(path("/graphql"))
+        .and(body_string_contains("pageInfo"))
+        .and(body_partial_json(
+            json!({ "variables": { "number": number } }),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(discussion))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn discussions() {
+    let server = MockServer::start().await;
+    let uri = server.uri();
+    let minutes = format!("{uri}/2024/11/21-vc-minutes.html");
+    Mock::given(method("GET"))
+        .and(path("/2024/11/21-vc-minutes.html"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(DISCUSSION_MINUTES))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/groups/wg/vc/repositories.json"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!([{ "name": "vc", "owner": { "login": "w3c" } }])),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/user"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(user()))
+        .mount(&server)
+        .await;
+
+    mock_discussion(&server, 5, vec![]).await;
+    let marker = crate::marker::Marker {
+        minutes: minutes.clone(),
+        anchors: vec!["t02".into()],
+        date: chrono::NaiveDate::from_ymd_opt(2024, 11, 21).unwrap(),
+        groups: "wg/vc".into(),
+        version: "0.1.0".into(),
+    };
+    let stale = discussion_comment(6, 61, &format!("See {minutes}#t01\n\n{marker}"));
+    mock_discussion(&server, 6, vec![stale]).await;
+    let linked = discussion_comment(7, 71, &format!("See {minutes}#t03"));
+    mock_discussion(&server, 7, vec![linked]).await;
+    // the shorthand #8 is first looked up as an issue
+    Mock::given(method("GET"))
+        .and(path("/repos/w3c/vc/issues/8"))
+        .respond_with(ResponseTemplate::new(404).set_body_json(json!({ "message": "Not Found" })))
+        .mount(&server)
+        .await;
+    mock_discussion(&server, 8, vec![]).await;
+
+    let added =
+        json!({ "data": { "addDiscussionComment": { "comment": discussion_comment(5, 51, "") } } });
+    Mock::given(method("POST"))
+        .and(path("/graphql"))
+        .and(body_string_contains("addDiscussionComment"))
+        .and(body_partial_json(json!({ "variables": { "id": "D5" } })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(added))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let added =
+        json!({ "data": { "addDiscussionComment": { "comment": discussion_comment(8, 81, "") } } });
+    Mock::given(method("POST"))
+        .and(path("/graphql"))
+        .and(body_string_contains("addDiscussionComment"))
+        .and(body_partial_json(json!({ "variables": { "id": "D8" } })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(added))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let updated = json!({ "data": { "updateDiscussionComment": { "comment": discussion_comment(6, 61, "") } } });
+    Mock::given(method("POST"))
+        .and(path("/graphql"))
+        .and(body_string_contains("updateDiscussionComment"))
+        .and(body_partial_json(json!({ "variables": { "id": "DC61" } })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(updated))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let args = ["--url", &minutes, "--default-repository", "vc"];
+    let (github, args) = manual_args(&server, &args);
+    let engine = Engine::new(github, args.engine).await.unwrap();
+    let outcomes: Vec<Outcome> = engine.run().try_collect().await.unwrap();
+    let got: Vec<String> = outcomes.iter().map(describe).collect();
+    assert_eq!(
+        got,
+        vec![
+            "created https://github.com/w3c/vc/discussions/5#discussioncomment-51",
+            "updated https://github.com/w3c/vc/discussions/6#discussioncomment-61",
+            "duplicate https://github.com/w3c/vc/discussions/7#discussioncomment-71",
+            "created https://github.com/w3c/vc/discussions/8#discussioncomment-81",
+        ]
+    );
+    // the new comment links to the minutes, and carries a marker
+    let requests = server.received_requests().await.unwrap();
+    let added: Vec<Value> = requests
+        .iter()
+        .map(|r| r.body_json::<Value>().unwrap_or_default())
+        .filter(|b| {
+            b["query"]
+                .as_str()
+                .unwrap_or("")
+                .contains("addDiscussionComment")
+        })
+        .collect();
+    let body = added[0]["variables"]["body"].as_str().unwrap();
+    assert!(body.contains(&format!("{minutes}#t01")));
+    assert!(body.contains("<!-- minutes_to_gh "));
+}
+
+#[test_case("m2g" => "updated https://github.com/w3c/vc/issues/2#issuecomment-21"; "mine")]
+#[test_case("someone" => "duplicate https://github.com/w3c/vc/issues/2#issuecomment-21"; "not mine")]
+#[tokio::test]
+async fn stale(author: &str) -> String {
+    let server = mock_server().await;
+    let minutes = format!("{}/2024/11/14-vc-minutes.html", server.uri());
+    let marker = crate::marker::Marker {
+        minutes: minutes.clone(),
+        anchors: vec!["t02".into()],
+        date: chrono::NaiveDate::from_ymd_opt(2024, 11, 14).unwrap(),
+        groups: "wg/vc".into(),
+        version: "0.1.0".into(),
+    };
+    let mut previous = comment("w3c/vc", 2, 21, &format!("See {minutes}#t01\n\n{marker}"));
+    previous["user"]["login"] = author.into();
+    Mock::given(method("GET"))
+        .and(path("/repos/w3c/vc/issues/2/comments"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(vec![previous]))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/user"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(user()))
+        .mount(&server)
+        .await;
+    // octocrab updates comments with POST rather than PATCH
+    Mock::given(method("POST"))
+        .and(path("/repos/w3c/vc/issues/comments/21"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(comment("w3c/vc", 2, 21, "")))
+        .mount(&server)
+        .await;
+
+    let got = run(&server, &[]).await;
+    got[1].clone()
+}
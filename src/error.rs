@@ -2,6 +2,8 @@ use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum EngineCreationError {
+    #[error("No IRC channel given")]
+    MissingChannel,
     #[error("Minutes not found <{0}>")]
     MinutesNotFound(String, #[source] reqwest::Error),
     #[error("Error loading minutes")]
@@ -16,6 +18,12 @@ pub enum EngineCreationError {
     W3cApi(#[source] reqwest::Error),
     #[error("GitHub API error")]
     GitHub(#[from] octocrab::Error),
+    #[error("Invalid GitHub credentials")]
+    Credentials(#[from] GitHubError),
+    #[error("Invalid comment template")]
+    Template(#[from] TemplateError),
+    #[error("Invalid participants mapping")]
+    Participants(#[from] ParticipantsError),
 }
 
 impl EngineCreationError {
@@ -31,3 +39,59 @@ impl EngineCreationError {
         Self::W3cApi(err)
     }
 }
+
+#[derive(Error, Debug)]
+pub enum TemplateError {
+    #[error("unknown placeholder {{{0}}}")]
+    UnknownPlaceholder(String),
+    #[error("missing placeholder {{{0}}}")]
+    MissingPlaceholder(&'static str),
+    #[error("unclosed placeholder {{{0}")]
+    Unclosed(String),
+    #[error("unexpected '}}' (use '}}}}' for a literal brace)")]
+    UnexpectedBrace,
+    #[error("Failed loading template from {0}")]
+    File(String, #[source] std::io::Error),
+    #[error("Invalid template in {0}")]
+    InFile(String, #[source] Box<TemplateError>),
+}
+
+#[derive(Error, Debug)]
+pub enum ParticipantsError {
+    #[error("Failed loading participants mapping from {0}")]
+    File(String, #[source] std::io::Error),
+    #[error("Invalid participants mapping in {0} (expected a JSON object)")]
+    Parse(String, #[source] serde_json::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum GitHubError {
+    #[error("either a token or an app id and private key must be provided")]
+    NoCredentials,
+    #[error("Failed loading private key from {0}")]
+    PrivateKeyFile(String, #[source] std::io::Error),
+    #[error("Invalid private key in {0} (expected an RSA key in PEM format)")]
+    PrivateKey(String, #[source] jsonwebtoken::errors::Error),
+    #[error("GitHub API error")]
+    Api(#[from] octocrab::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum RunRecordError {
+    #[error("Failed accessing run record {0}")]
+    File(String, #[source] std::io::Error),
+    #[error("Invalid run record in {0}")]
+    Parse(String, #[source] serde_json::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum ManifestError {
+    #[error("Failed loading manifest from {0}")]
+    File(String, #[source] std::io::Error),
+    #[error("Invalid manifest in {0} (expected a JSON array of entries)")]
+    Parse(String, #[source] serde_json::Error),
+}
+
+#[derive(Error, Debug)]
+#[error("Failed writing to ledger {0}")]
+pub struct LedgerError(pub String, #[source] pub std::io::Error);
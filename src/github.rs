@@ -0,0 +1,121 @@
+//! I define the clients used to access the GitHub APIs, depending on the configured credentials.
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use jsonwebtoken::EncodingKey;
+use octocrab::{Octocrab, models::AppId};
+use secrecy::ExposeSecret;
+
+use crate::args::GitHubArgs;
+use crate::error::GitHubError;
+
+/// How long an installation token is reused before requesting a new one
+/// (GitHub makes them expire after one hour).
+const INSTALLATION_TOKEN_LIFETIME: Duration = Duration::from_secs(50 * 60);
+
+/// Clients for the REST and the GraphQL APIs, sharing the same credentials.
+#[derive(Clone, Debug)]
+pub struct Clients {
+    pub rest: Octocrab,
+    /// The client for the GraphQL API (whose base URL differs from the REST API's on GitHub Enterprise Server)
+    pub graphql: Octocrab,
+}
+
+impl Clients {
+    #[expect(clippy::result_large_err)]
+    fn with_token(args: &GitHubArgs, token: &str) -> octocrab::Result<Self> {
+        Ok(Self {
+            rest: Octocrab::builder()
+                .base_uri(args.api_url())?
+                .personal_token(token.to_string())
+                .build()?,
+            graphql: Octocrab::builder()
+                .base_uri(args.graphql_base_url())?
+                .personal_token(token.to_string())
+                .build()?,
+        })
+    }
+}
+
+/// The source of [`Clients`]: either a personal token, or a GitHub App.
+pub enum GitHub {
+    /// The same clients are used for every repository.
+    Token(Clients),
+    /// Each repository owner has its own installation of the app, hence its own clients.
+    App {
+        app: Octocrab,
+        args: GitHubArgs,
+        installations: Mutex<HashMap<String, (Instant, Clients)>>,
+    },
+}
+
+impl GitHub {
+    #[expect(clippy::result_large_err)]
+    pub fn new(args: &GitHubArgs) -> Result<Self, GitHubError> {
+        match (&args.token, args.app_id, &args.app_private_key) {
+            (Some(token), _, _) => Ok(Self::Token(Clients::with_token(args, token)?)),
+            (None, Some(app_id), Some(path)) => {
+                let pem = std::fs::read(path)
+                    .map_err(|err| GitHubError::PrivateKeyFile(path.clone(), err))?;
+                let key = EncodingKey::from_rsa_pem(&pem)
+                    .map_err(|err| GitHubError::PrivateKey(path.clone(), err))?;
+                let app = Octocrab::builder()
+                    .base_uri(args.api_url())?
+                    .app(AppId(app_id), key)
+                    .build()?;
+                Ok(Self::App {
+                    app,
+                    args: args.clone(),
+                    installations: Mutex::default(),
+                })
+            }
+            _ => Err(GitHubError::NoCredentials),
+        }
+    }
+
+    /// The login of the account on behalf of which comments are posted
+    /// (for an app, the login of its bot account).
+    pub async fn login(&self) -> octocrab::Result<String> {
+        match self {
+            Self::Token(clients) => Ok(clients.rest.current().user().await?.login),
+            Self::App { app, .. } => {
+                let app = app.current().app().await?;
+                Ok(format!("{}[bot]", app.slug.unwrap_or(app.name)))
+            }
+        }
+    }
+
+    /// The clients to use for the given repository.
+    ///
+    /// In app mode, the installation of the app for the owner of the repository is looked up,
+    /// and its token is cached (per owner) until it is about to expire.
+    pub async fn clients(&self, owner: &str, repo: &str) -> octocrab::Result<Clients> {
+        let (app, args, installations) = match self {
+            Self::Token(clients) => return Ok(clients.clone()),
+            Self::App {
+                app,
+                args,
+                installations,
+            } => (app, args, installations),
+        };
+        let key = owner.to_ascii_lowercase();
+        if let Some((since, clients)) = installations.lock().unwrap().get(&key)
+            && since.elapsed() < INSTALLATION_TOKEN_LIFETIME
+        {
+            return Ok(clients.clone());
+        }
+        let installation = app.apps().get_repository_installation(owner, repo).await?;
+        log::debug!("Using installation {} for {owner}", installation.id);
+        let (_, token) = app.installation_and_token(installation.id).await?;
+        let clients = Clients::with_token(args, token.expose_secret())?;
+        installations
+            .lock()
+            .unwrap()
+            .insert(key, (Instant::now(), clients.clone()));
+        Ok(clients)
+    }
+}
@@ -6,37 +6,70 @@ use irc::client::prelude::*;
 use regex::{Regex, RegexBuilder};
 
 use std::{
+    collections::HashMap,
     sync::{
-        LazyLock,
+        Arc, LazyLock, Mutex,
         atomic::{AtomicUsize, Ordering::SeqCst},
     },
     time::Duration,
 };
 
 use crate::{
-    args::{EngineArgs, FinitePositiveF64, IrcBotArgs},
-    engine::Engine,
+    args::{
+        EngineArgs, FinitePositiveF64, GROUPS_URL, GitHubArgs, IrcBotArgs, LabelArgs, MINUTES_URL,
+        RecordArgs, TemplateArgs,
+    },
+    engine::{Backend, Engine},
     error::EngineCreationError::MinutesNotFound,
+    github::GitHub,
+    minutes::Heuristic,
     outcome::{
         Outcome,
-        OutcomeKind::{Created, Duplicate, Error, Faked, NotOwned},
+        OutcomeKind::{
+            ArchivedRepo, Closed, Created, Deleted, Duplicate, Error, Faked, LabelError, Labeled,
+            Locked, NotFound, NotOwned, Truncated, TruncatedUpdate, Unlabeled, Unmarked, Updated,
+        },
     },
+    participants::Participants,
+    revert::{revert, reverted},
+    runs::RunRecord,
+    template::Templates,
 };
 
-pub async fn command(token: String, args: IrcBotArgs) -> Result<()> {
-    Bot::new(token, args).await?.poll().await?;
+pub async fn command(github: GitHubArgs, args: IrcBotArgs) -> Result<()> {
+    Bot::new(github, args).await?.poll().await?;
     Ok(())
 }
 
 struct Bot {
     client: Client,
-    token: String,
+    github_args: GitHubArgs,
+    /// Shared by all runs, so that installation tokens are cached when authenticating as a GitHub App
+    github: Arc<GitHub>,
     governor: DefaultKeyedRateLimiter<String>,
+    default_repositories: HashMap<String, String>,
+    participants: Option<String>,
+    templates: TemplateArgs,
+    labels: LabelArgs,
+    skip_closed: bool,
+    records: RecordArgs,
 }
 
 impl Bot {
-    async fn new(token: String, args: IrcBotArgs) -> Result<Self> {
+    async fn new(github_args: GitHubArgs, args: IrcBotArgs) -> Result<Self> {
         log::info!("Connecting to {}:{}", args.server, args.port);
+        let default_repositories = args.default_repositories.iter().cloned().collect();
+        // validate credentials and templates before anything is posted
+        let github = Arc::new(GitHub::new(&github_args)?);
+        Templates::load(&args.templates)?;
+        let templates = args.templates.clone();
+        if let Some(path) = &args.participants {
+            Participants::from_file(path)?;
+        }
+        let participants = args.participants.clone();
+        let labels = args.labels.clone();
+        let skip_closed = args.skip_closed;
+        let records = args.records.clone();
         let client = Client::from_config(args.into()).await?;
         // identify comes from ClientExt
         client.identify()?;
@@ -45,8 +78,15 @@ impl Bot {
             RateLimiter::keyed(Quota::with_period(Duration::from_secs_f64(1.0)).unwrap());
         Ok(Self {
             client,
-            token,
+            github_args,
+            github,
             governor,
+            default_repositories,
+            participants,
+            templates,
+            labels,
+            skip_closed,
+            records,
         })
     }
 
@@ -71,11 +111,26 @@ impl Bot {
                         let res = match cmd {
                             BotCommand::Bye => self.bye(channel).await,
                             BotCommand::Help => self.help(&message).await,
-                            BotCommand::LinkIssues(transcript, groups) => {
-                                self.link_issues(transcript, groups, &message).await
+                            BotCommand::LinkIssues(
+                                transcript,
+                                resolutions,
+                                participants,
+                                groups,
+                                heuristic,
+                            ) => {
+                                self.link_issues(
+                                    transcript,
+                                    resolutions,
+                                    participants,
+                                    groups,
+                                    heuristic,
+                                    &message,
+                                )
+                                .await
                             }
-                            BotCommand::Debug(date, groups) => {
-                                self.debug(date, groups, &message).await
+                            BotCommand::Undo => self.undo(&message).await,
+                            BotCommand::Debug(date, groups, heuristic) => {
+                                self.debug(date, groups, heuristic, &message).await
                             }
                             BotCommand::Unrecognized => self.unrecognized(&message, cmd_str).await,
                         };
@@ -147,7 +202,10 @@ impl Bot {
     async fn link_issues(
         &self,
         transcript: bool,
+        resolutions: bool,
+        participants: bool,
         groups: Option<&str>,
+        heuristic: Option<Heuristic>,
         message: &Message,
     ) -> Result<()> {
         debug_assert!(matches!(message.command, Command::PRIVMSG(..)));
@@ -156,14 +214,28 @@ impl Bot {
         self.do_link_issues(
             message,
             EngineArgs {
-                channel: message.response_target().unwrap().to_string(),
+                channel: message.response_target().map(ToString::to_string),
                 date: Some(chrono::offset::Local::now().date_naive()),
                 transcript,
+                resolutions,
+                mention_participants: participants,
                 groups: groups.map(ToString::to_string),
                 rate_limit: FinitePositiveF64::new_unchecked(1.0),
+                skip_closed: self.skip_closed,
+                look_back: 1,
                 dry_run: false,
+                requested_by: message.source_nickname().map(ToString::to_string),
                 url: None,
+                minutes_url: MINUTES_URL.into(),
+                groups_url: GROUPS_URL.into(),
                 file: None,
+                format: None,
+                default_repository: self.default_repository(message),
+                heuristic: heuristic.unwrap_or_default(),
+                participants: self.participants.clone(),
+                templates: self.templates.clone(),
+                labels: self.labels.clone(),
+                records: self.records.clone(),
                 extra_repositories: vec![],
             },
         )
@@ -174,6 +246,7 @@ impl Bot {
         &self,
         date: Option<&str>,
         groups: Option<&str>,
+        heuristic: Option<Heuristic>,
         message: &Message,
     ) -> Result<()> {
         debug_assert!(matches!(message.command, Command::PRIVMSG(..)));
@@ -196,14 +269,32 @@ impl Bot {
         self.do_link_issues(
             message,
             EngineArgs {
-                channel: message.response_target().unwrap().to_string(),
+                channel: message.response_target().map(ToString::to_string),
                 date,
                 transcript: true,
+                resolutions: true,
+                mention_participants: true,
                 groups,
                 rate_limit: FinitePositiveF64::new_unchecked(1.0),
+                skip_closed: self.skip_closed,
+                look_back: 1,
                 dry_run: true,
+                requested_by: message.source_nickname().map(ToString::to_string),
                 url: None,
+                minutes_url: MINUTES_URL.into(),
+                groups_url: GROUPS_URL.into(),
                 file: None,
+                format: None,
+                default_repository: self.default_repository(message),
+                heuristic: heuristic.unwrap_or_default(),
+                participants: self.participants.clone(),
+                templates: self.templates.clone(),
+                labels: self.labels.clone(),
+                // dry runs are recorded in the ledger, but not for undo
+                records: RecordArgs {
+                    runs_dir: None,
+                    ledger: self.records.ledger.clone(),
+                },
                 extra_repositories: vec![],
             },
         )
@@ -214,7 +305,9 @@ impl Bot {
         debug_assert!(matches!(message.command, Command::PRIVMSG(..)));
 
         let engine =
-            match Engine::new(self.token.clone(), args.clone()).await {
+            match Engine::with_backend(self.github_args.clone(), args.clone(), self.backend(&args))
+                .await
+            {
                 Ok(engine) => engine,
                 Err(MinutesNotFound(..)) => {
                     static MSG: &str =
@@ -225,7 +318,8 @@ impl Bot {
                         Some(args.date.unwrap().pred_opt().ok_or_else(|| {
                             anyhow::anyhow!("Could not build date for yesterday")
                         })?);
-                    Engine::new(self.token.clone(), args).await?
+                    let backend = self.backend(&args);
+                    Engine::with_backend(self.github_args.clone(), args, backend).await?
                 }
                 Err(err) => Err(err)?,
             };
@@ -235,38 +329,7 @@ impl Bot {
             .run()
             .try_for_each(|outcome: Outcome| async move {
                 cref.fetch_add(1, SeqCst);
-                let issue = &outcome.issue;
-                match outcome.kind {
-                    Created(comment) => {
-                        self.respond(message, &format!("comment created: {comment}"))
-                            .await
-                    }
-                    Faked => {
-                        self.respond(
-                            message,
-                            &format!("comment would have been created for: {issue}"),
-                        )
-                        .await
-                    }
-                    Duplicate(comment) => {
-                        self.respond(message, &format!("comment already there: {comment}"))
-                            .await
-                    }
-                    NotOwned => {
-                        self.respond(
-                            message,
-                            &format!("issue {issue} not owned by current group(s)"),
-                        )
-                        .await
-                    }
-                    Error(_) => {
-                        self.respond(
-                            message,
-                            &format!("a problem occurred when processing {issue}"),
-                        )
-                        .await
-                    }
-                }
+                self.respond(message, &describe(outcome)).await
             })
             .await?;
         if c.load(SeqCst) == 0 {
@@ -276,6 +339,55 @@ impl Bot {
         Ok(())
     }
 
+    /// A backend for a run with the given arguments, sharing the GitHub client of the bot.
+    fn backend(&self, args: &EngineArgs) -> Backend {
+        Backend::new(self.github.clone(), args.rate_limit)
+    }
+
+    /// Delete the comments created by the last run on the channel of the given message (if not reverted yet).
+    async fn undo(&self, message: &Message) -> Result<()> {
+        debug_assert!(matches!(message.command, Command::PRIVMSG(..)));
+
+        let Some(dir) = &self.records.runs_dir else {
+            return self
+                .respond(
+                    message,
+                    "sorry, I can not undo anything (runs are not recorded)",
+                )
+                .await;
+        };
+        let channel = message.response_target().unwrap();
+        log::info!("Undoing last run on {channel}");
+        let Some(record) = RunRecord::last(dir, channel)? else {
+            return self.respond(message, "nothing to undo").await;
+        };
+        self.respond(
+            message,
+            &format!(
+                "deleting the {} comment(s) linking to {}",
+                record.comments.len(),
+                record.minutes,
+            ),
+        )
+        .await?;
+        let failed = Mutex::new(vec![]);
+        let fref = &failed;
+        revert(&self.github, &self.github_args.github_host, &record)
+            .try_for_each(|outcome: Outcome| async move {
+                fref.lock().unwrap().push(matches!(outcome.kind, Error(_)));
+                self.respond(message, &describe(outcome)).await
+            })
+            .await?;
+        reverted(record, &failed.into_inner().unwrap()).save(dir)?;
+        Ok(())
+    }
+
+    /// The default repository configured for the channel of the given message, if any.
+    fn default_repository(&self, message: &Message) -> Option<String> {
+        let channel = message.response_target()?.trim_start_matches('#');
+        self.default_repositories.get(channel).cloned()
+    }
+
     async fn unrecognized(&self, message: &Message, cmd_str: &str) -> Result<()> {
         debug_assert!(matches!(message.command, Command::PRIVMSG(..)));
 
@@ -309,8 +421,9 @@ impl Bot {
 enum BotCommand<'a> {
     Bye,
     Help,
-    LinkIssues(bool, Option<&'a str>),
-    Debug(Option<&'a str>, Option<&'a str>),
+    LinkIssues(bool, bool, bool, Option<&'a str>, Option<Heuristic>),
+    Debug(Option<&'a str>, Option<&'a str>, Option<Heuristic>),
+    Undo,
     Unrecognized,
 }
 
@@ -329,24 +442,37 @@ impl<'a> From<&'a str> for BotCommand<'a> {
     fn from(value: &'a str) -> Self {
         use BotCommand::*;
 
-        lazy_re! { LINK_ISSUES = "^(please )?(back)?link (github )?issues( to minutes)?(?<transcript> with transcript)?( for (?<groups>[^ ]+))?$" }
+        lazy_re! { LINK_ISSUES = "^(please )?(back)?link (github )?issues( to minutes)?( with (?<with>(transcript|resolutions|participants)((, | and |, and )(transcript|resolutions|participants))*))?( for (?<groups>[^ ]+))?( using (?<heuristic>ancestor|relevant|closest)( heuristic)?)?$" }
         lazy_re! { HELP = "^(please )?help$" }
+        lazy_re! { UNDO = "^(please )?undo( (the )?last run)?$" }
         lazy_re! { BYE = "^bye|out|(please )?(excuse us|leave|part)$" }
-        lazy_re! { DEBUG= "^debug( date (?<date>[^ ]+))?( groups (?<groups>[^ ]+))?$" }
+        lazy_re! { DEBUG= "^debug( date (?<date>[^ ]+))?( groups (?<groups>[^ ]+))?( using (?<heuristic>ancestor|relevant|closest)( heuristic)?)?$" }
+        let heuristic = |captures: &regex::Captures| {
+            captures
+                .name("heuristic")
+                .and_then(|m| m.as_str().parse().ok())
+        };
 
         if let Some(captures) = LINK_ISSUES.captures(value) {
+            let with = captures.name("with").map(|m| m.as_str()).unwrap_or("");
             LinkIssues(
-                captures.name("transcript").is_some(),
+                with.contains("transcript"),
+                with.contains("resolutions"),
+                with.contains("participants"),
                 captures.name("groups").map(|m| m.as_str()),
+                heuristic(&captures),
             )
         } else if HELP.is_match(value) {
             Help
+        } else if UNDO.is_match(value) {
+            Undo
         } else if BYE.is_match(value) {
             Bye
         } else if let Some(captures) = DEBUG.captures(value) {
             Debug(
                 captures.name("date").map(|m| m.as_str()),
                 captures.name("groups").map(|m| m.as_str()),
+                heuristic(&captures),
             )
         } else {
             Unrecognized
@@ -354,6 +480,40 @@ impl<'a> From<&'a str> for BotCommand<'a> {
     }
 }
 
+/// A one-line description of the given outcome, to be sent on IRC.
+fn describe(outcome: Outcome) -> String {
+    let issue = &outcome.issue;
+    let moved = outcome
+        .moved_from
+        .map(|original| format!(" (moved from {original})"))
+        .unwrap_or_default();
+    match outcome.kind {
+        Created(comment) => format!("comment created: {comment}{moved}"),
+        Truncated(comment) => {
+            format!("comment created (with truncated transcript): {comment}{moved}")
+        }
+        Updated(comment) => format!("comment updated: {comment}{moved}"),
+        TruncatedUpdate(comment) => {
+            format!("comment updated (with truncated transcript): {comment}{moved}")
+        }
+        Deleted(comment) => format!("comment deleted: {comment}"),
+        Unmarked(comment) => format!("comment {comment} not deleted, as it was not posted by me"),
+        Labeled(label) => format!("label {label} added to {issue}"),
+        Unlabeled(label) => format!("label {label} removed from {issue}"),
+        LabelError(label, _) => {
+            format!("comment posted, but a problem occurred when updating label {label} of {issue}")
+        }
+        Faked => format!("comment would have been created for: {issue}{moved}"),
+        Duplicate(comment) => format!("comment already there: {comment}{moved}"),
+        NotOwned => format!("issue {issue} not owned by current group(s){moved}"),
+        Locked => format!("issue {issue} is locked, not commented{moved}"),
+        ArchivedRepo => format!("repository of {issue} is archived, not commented{moved}"),
+        NotFound => format!("issue {issue} not found (deleted, or not visible with my token)"),
+        Closed => format!("issue {issue} is closed, not commented{moved}"),
+        Error(_) => format!("a problem occurred when processing {issue}{moved}"),
+    }
+}
+
 /// Version of Message:response_target that returns &Strings instead of &str,
 /// so that we can pass it as keys to Bot::governor
 fn my_response_target<'a>(target: &'a String, msg: &'a Message) -> Option<&'a String> {
@@ -381,74 +541,86 @@ mod test {
     #[test_case("please part" => BotCommand::Bye)]
     #[test_case("help" => BotCommand::Help)]
     #[test_case("please help" => BotCommand::Help)]
-    #[test_case("debug" => BotCommand::Debug(None, None))]
-    #[test_case("debug date 2024-11-14" => BotCommand::Debug(Some("2024-11-14"), None))]
-    #[test_case("debug date 2024-11-14 groups wg/did,cg/credentials" => BotCommand::Debug(Some("2024-11-14"), Some("wg/did,cg/credentials")))]
-    #[test_case("debug groups wg/did,cg/credentials" => BotCommand::Debug(None, Some("wg/did,cg/credentials")))]
-    #[test_case("backlink github issues" => BotCommand::LinkIssues(false, None))]
-    #[test_case("backlink github issues for wg/foo,cg/bar" => BotCommand::LinkIssues(false, Some("wg/foo,cg/bar")))]
-    #[test_case("backlink github issues to minutes" => BotCommand::LinkIssues(false, None))]
-    #[test_case("backlink github issues to minutes for wg/foo,cg/bar" => BotCommand::LinkIssues(false, Some("wg/foo,cg/bar")))]
-    #[test_case("backlink github issues to minutes with transcript" => BotCommand::LinkIssues(true, None))]
-    #[test_case("backlink github issues to minutes with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, Some("wg/foo,cg/bar")))]
-    #[test_case("backlink github issues with transcript" => BotCommand::LinkIssues(true, None))]
-    #[test_case("backlink github issues with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, Some("wg/foo,cg/bar")))]
-    #[test_case("backlink issues" => BotCommand::LinkIssues(false, None))]
-    #[test_case("backlink issues for wg/foo,cg/bar" => BotCommand::LinkIssues(false, Some("wg/foo,cg/bar")))]
-    #[test_case("backlink issues to minutes" => BotCommand::LinkIssues(false, None))]
-    #[test_case("backlink issues to minutes for wg/foo,cg/bar" => BotCommand::LinkIssues(false, Some("wg/foo,cg/bar")))]
-    #[test_case("backlink issues to minutes with transcript" => BotCommand::LinkIssues(true, None))]
-    #[test_case("backlink issues to minutes with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, Some("wg/foo,cg/bar")))]
-    #[test_case("backlink issues with transcript" => BotCommand::LinkIssues(true, None))]
-    #[test_case("backlink issues with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, Some("wg/foo,cg/bar")))]
-    #[test_case("link github issues" => BotCommand::LinkIssues(false, None))]
-    #[test_case("link github issues for wg/foo,cg/bar" => BotCommand::LinkIssues(false, Some("wg/foo,cg/bar")))]
-    #[test_case("link github issues to minutes" => BotCommand::LinkIssues(false, None))]
-    #[test_case("link github issues to minutes for wg/foo,cg/bar" => BotCommand::LinkIssues(false, Some("wg/foo,cg/bar")))]
-    #[test_case("link github issues to minutes with transcript" => BotCommand::LinkIssues(true, None))]
-    #[test_case("link github issues to minutes with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, Some("wg/foo,cg/bar")))]
-    #[test_case("link github issues with transcript" => BotCommand::LinkIssues(true, None))]
-    #[test_case("link github issues with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, Some("wg/foo,cg/bar")))]
-    #[test_case("link issues" => BotCommand::LinkIssues(false, None))]
-    #[test_case("link issues for wg/foo,cg/bar" => BotCommand::LinkIssues(false, Some("wg/foo,cg/bar")))]
-    #[test_case("link issues to minutes" => BotCommand::LinkIssues(false, None))]
-    #[test_case("link issues to minutes for wg/foo,cg/bar" => BotCommand::LinkIssues(false, Some("wg/foo,cg/bar")))]
-    #[test_case("link issues to minutes with transcript" => BotCommand::LinkIssues(true, None))]
-    #[test_case("link issues to minutes with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, Some("wg/foo,cg/bar")))]
-    #[test_case("link issues with transcript" => BotCommand::LinkIssues(true, None))]
-    #[test_case("link issues with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, Some("wg/foo,cg/bar")))]
-    #[test_case("please backlink github issues" => BotCommand::LinkIssues(false, None))]
-    #[test_case("please backlink github issues for wg/foo,cg/bar" => BotCommand::LinkIssues(false, Some("wg/foo,cg/bar")))]
-    #[test_case("please backlink github issues to minutes" => BotCommand::LinkIssues(false, None))]
-    #[test_case("please backlink github issues to minutes for wg/foo,cg/bar" => BotCommand::LinkIssues(false, Some("wg/foo,cg/bar")))]
-    #[test_case("please backlink github issues to minutes with transcript" => BotCommand::LinkIssues(true, None))]
-    #[test_case("please backlink github issues to minutes with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, Some("wg/foo,cg/bar")))]
-    #[test_case("please backlink github issues with transcript" => BotCommand::LinkIssues(true, None))]
-    #[test_case("please backlink github issues with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, Some("wg/foo,cg/bar")))]
-    #[test_case("please backlink issues" => BotCommand::LinkIssues(false, None))]
-    #[test_case("please backlink issues for wg/foo,cg/bar" => BotCommand::LinkIssues(false, Some("wg/foo,cg/bar")))]
-    #[test_case("please backlink issues to minutes" => BotCommand::LinkIssues(false, None))]
-    #[test_case("please backlink issues to minutes for wg/foo,cg/bar" => BotCommand::LinkIssues(false, Some("wg/foo,cg/bar")))]
-    #[test_case("please backlink issues to minutes with transcript" => BotCommand::LinkIssues(true, None))]
-    #[test_case("please backlink issues to minutes with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, Some("wg/foo,cg/bar")))]
-    #[test_case("please backlink issues with transcript" => BotCommand::LinkIssues(true, None))]
-    #[test_case("please backlink issues with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, Some("wg/foo,cg/bar")))]
-    #[test_case("please link github issues" => BotCommand::LinkIssues(false, None))]
-    #[test_case("please link github issues for wg/foo,cg/bar" => BotCommand::LinkIssues(false, Some("wg/foo,cg/bar")))]
-    #[test_case("please link github issues to minutes" => BotCommand::LinkIssues(false, None))]
-    #[test_case("please link github issues to minutes for wg/foo,cg/bar" => BotCommand::LinkIssues(false, Some("wg/foo,cg/bar")))]
-    #[test_case("please link github issues to minutes with transcript" => BotCommand::LinkIssues(true, None))]
-    #[test_case("please link github issues to minutes with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, Some("wg/foo,cg/bar")))]
-    #[test_case("please link github issues with transcript" => BotCommand::LinkIssues(true, None))]
-    #[test_case("please link github issues with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, Some("wg/foo,cg/bar")))]
-    #[test_case("please link issues" => BotCommand::LinkIssues(false, None))]
-    #[test_case("please link issues for wg/foo,cg/bar" => BotCommand::LinkIssues(false, Some("wg/foo,cg/bar")))]
-    #[test_case("please link issues to minutes" => BotCommand::LinkIssues(false, None))]
-    #[test_case("please link issues to minutes for wg/foo,cg/bar" => BotCommand::LinkIssues(false, Some("wg/foo,cg/bar")))]
-    #[test_case("please link issues to minutes with transcript" => BotCommand::LinkIssues(true, None))]
-    #[test_case("please link issues to minutes with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, Some("wg/foo,cg/bar")))]
-    #[test_case("please link issues with transcript" => BotCommand::LinkIssues(true, None))]
-    #[test_case("please link issues with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, Some("wg/foo,cg/bar")))]
+    #[test_case("debug" => BotCommand::Debug(None, None, None))]
+    #[test_case("debug date 2024-11-14" => BotCommand::Debug(Some("2024-11-14"), None, None))]
+    #[test_case("debug date 2024-11-14 groups wg/did,cg/credentials" => BotCommand::Debug(Some("2024-11-14"), Some("wg/did,cg/credentials"), None))]
+    #[test_case("debug groups wg/did,cg/credentials" => BotCommand::Debug(None, Some("wg/did,cg/credentials"), None))]
+    #[test_case("backlink github issues" => BotCommand::LinkIssues(false, false, false, None, None))]
+    #[test_case("backlink github issues for wg/foo,cg/bar" => BotCommand::LinkIssues(false, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("backlink github issues to minutes" => BotCommand::LinkIssues(false, false, false, None, None))]
+    #[test_case("backlink github issues to minutes for wg/foo,cg/bar" => BotCommand::LinkIssues(false, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("backlink github issues to minutes with transcript" => BotCommand::LinkIssues(true, false, false, None, None))]
+    #[test_case("backlink github issues to minutes with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("backlink github issues with transcript" => BotCommand::LinkIssues(true, false, false, None, None))]
+    #[test_case("backlink github issues with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("backlink issues" => BotCommand::LinkIssues(false, false, false, None, None))]
+    #[test_case("backlink issues for wg/foo,cg/bar" => BotCommand::LinkIssues(false, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("backlink issues to minutes" => BotCommand::LinkIssues(false, false, false, None, None))]
+    #[test_case("backlink issues to minutes for wg/foo,cg/bar" => BotCommand::LinkIssues(false, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("backlink issues to minutes with transcript" => BotCommand::LinkIssues(true, false, false, None, None))]
+    #[test_case("backlink issues to minutes with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("backlink issues with transcript" => BotCommand::LinkIssues(true, false, false, None, None))]
+    #[test_case("backlink issues with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("link github issues" => BotCommand::LinkIssues(false, false, false, None, None))]
+    #[test_case("link github issues for wg/foo,cg/bar" => BotCommand::LinkIssues(false, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("link github issues to minutes" => BotCommand::LinkIssues(false, false, false, None, None))]
+    #[test_case("link github issues to minutes for wg/foo,cg/bar" => BotCommand::LinkIssues(false, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("link github issues to minutes with transcript" => BotCommand::LinkIssues(true, false, false, None, None))]
+    #[test_case("link github issues to minutes with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("link github issues with transcript" => BotCommand::LinkIssues(true, false, false, None, None))]
+    #[test_case("link github issues with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("link issues" => BotCommand::LinkIssues(false, false, false, None, None))]
+    #[test_case("link issues for wg/foo,cg/bar" => BotCommand::LinkIssues(false, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("link issues to minutes" => BotCommand::LinkIssues(false, false, false, None, None))]
+    #[test_case("link issues to minutes for wg/foo,cg/bar" => BotCommand::LinkIssues(false, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("link issues to minutes with transcript" => BotCommand::LinkIssues(true, false, false, None, None))]
+    #[test_case("link issues to minutes with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("link issues with transcript" => BotCommand::LinkIssues(true, false, false, None, None))]
+    #[test_case("link issues with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("please backlink github issues" => BotCommand::LinkIssues(false, false, false, None, None))]
+    #[test_case("please backlink github issues for wg/foo,cg/bar" => BotCommand::LinkIssues(false, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("please backlink github issues to minutes" => BotCommand::LinkIssues(false, false, false, None, None))]
+    #[test_case("please backlink github issues to minutes for wg/foo,cg/bar" => BotCommand::LinkIssues(false, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("please backlink github issues to minutes with transcript" => BotCommand::LinkIssues(true, false, false, None, None))]
+    #[test_case("please backlink github issues to minutes with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("please backlink github issues with transcript" => BotCommand::LinkIssues(true, false, false, None, None))]
+    #[test_case("please backlink github issues with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("please backlink issues" => BotCommand::LinkIssues(false, false, false, None, None))]
+    #[test_case("please backlink issues for wg/foo,cg/bar" => BotCommand::LinkIssues(false, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("please backlink issues to minutes" => BotCommand::LinkIssues(false, false, false, None, None))]
+    #[test_case("please backlink issues to minutes for wg/foo,cg/bar" => BotCommand::LinkIssues(false, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("please backlink issues to minutes with transcript" => BotCommand::LinkIssues(true, false, false, None, None))]
+    #[test_case("please backlink issues to minutes with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("please backlink issues with transcript" => BotCommand::LinkIssues(true, false, false, None, None))]
+    #[test_case("please backlink issues with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("please link github issues" => BotCommand::LinkIssues(false, false, false, None, None))]
+    #[test_case("please link github issues for wg/foo,cg/bar" => BotCommand::LinkIssues(false, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("please link github issues to minutes" => BotCommand::LinkIssues(false, false, false, None, None))]
+    #[test_case("please link github issues to minutes for wg/foo,cg/bar" => BotCommand::LinkIssues(false, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("please link github issues to minutes with transcript" => BotCommand::LinkIssues(true, false, false, None, None))]
+    #[test_case("please link github issues to minutes with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("please link github issues with transcript" => BotCommand::LinkIssues(true, false, false, None, None))]
+    #[test_case("please link github issues with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("please link issues" => BotCommand::LinkIssues(false, false, false, None, None))]
+    #[test_case("please link issues for wg/foo,cg/bar" => BotCommand::LinkIssues(false, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("please link issues to minutes" => BotCommand::LinkIssues(false, false, false, None, None))]
+    #[test_case("please link issues to minutes for wg/foo,cg/bar" => BotCommand::LinkIssues(false, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("please link issues to minutes with transcript" => BotCommand::LinkIssues(true, false, false, None, None))]
+    #[test_case("please link issues to minutes with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("please link issues with transcript" => BotCommand::LinkIssues(true, false, false, None, None))]
+    #[test_case("please link issues with transcript for wg/foo,cg/bar" => BotCommand::LinkIssues(true, false, false, Some("wg/foo,cg/bar"), None))]
+    #[test_case("debug using closest" => BotCommand::Debug(None, None, Some(Heuristic::Closest)))]
+    #[test_case("debug date 2024-11-14 using ancestor heuristic" => BotCommand::Debug(Some("2024-11-14"), None, Some(Heuristic::Ancestor)))]
+    #[test_case("link issues using closest heuristic" => BotCommand::LinkIssues(false, false, false, None, Some(Heuristic::Closest)))]
+    #[test_case("link issues with transcript for wg/foo using relevant" => BotCommand::LinkIssues(true, false, false, Some("wg/foo"), Some(Heuristic::Relevant)))]
+    #[test_case("link issues using nearest heuristic" => BotCommand::Unrecognized)]
+    #[test_case("link issues with resolutions" => BotCommand::LinkIssues(false, true, false, None, None))]
+    #[test_case("link issues with transcript and resolutions for wg/foo" => BotCommand::LinkIssues(true, true, false, Some("wg/foo"), None))]
+    #[test_case("link issues with resolutions and transcript" => BotCommand::LinkIssues(true, true, false, None, None))]
+    #[test_case("link issues with participants" => BotCommand::LinkIssues(false, false, true, None, None))]
+    #[test_case("link issues with transcript, resolutions and participants for wg/foo" => BotCommand::LinkIssues(true, true, true, Some("wg/foo"), None))]
+    #[test_case("undo" => BotCommand::Undo)]
+    #[test_case("please undo the last run" => BotCommand::Undo)]
     #[test_case("anything else" => BotCommand::Unrecognized)]
     fn bot_command(txt: &str) -> BotCommand<'_> {
         BotCommand::from(txt)
@@ -0,0 +1,215 @@
+//! I define the ledger, an append-only log (in JSON Lines) of all runs and their outcomes, for audit purposes.
+
+use std::{fs::OpenOptions, io::Write};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::LedgerError;
+use crate::outcome::{Outcome, OutcomeKind};
+
+/// The entry of a run in the ledger (one line of the ledger file).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LedgerEntry {
+    pub run_id: String,
+    /// When the run started
+    pub started: DateTime<Utc>,
+    /// When the run ended (or was interrupted)
+    pub ended: DateTime<Utc>,
+    pub trigger: Trigger,
+    /// The IRC channel of the minutes (starting with '#')
+    pub channel: String,
+    pub date: NaiveDate,
+    pub groups: String,
+    /// The URL of the minutes
+    pub minutes: String,
+    pub dry_run: bool,
+    /// Whether all the mentioned issues were processed (false if the run was interrupted)
+    pub completed: bool,
+    pub outcomes: Vec<LedgerOutcome>,
+}
+
+/// Who triggered a run.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "via", rename_all = "lowercase")]
+pub enum Trigger {
+    Cli,
+    Irc { nick: String },
+}
+
+impl Trigger {
+    /// The trigger of a run requested by the given IRC nickname, or from the command line if `None`.
+    pub fn new(requested_by: Option<String>) -> Self {
+        match requested_by {
+            None => Self::Cli,
+            Some(nick) => Self::Irc { nick },
+        }
+    }
+}
+
+/// An [`Outcome`] as recorded in the ledger.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct LedgerOutcome {
+    /// The URL of the issue, pull request or discussion
+    pub issue: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moved_from: Option<String>,
+    /// The kind of outcome, in snake case (e.g. `created`, `not_owned`)
+    pub kind: String,
+    /// The URL of the comment, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// The label added or removed (or that could not be), if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// The chain of error messages, outermost first, if any
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub error: Vec<String>,
+}
+
+impl From<&Outcome> for LedgerOutcome {
+    fn from(outcome: &Outcome) -> Self {
+        use OutcomeKind::*;
+
+        let (comment, label, error) = match &outcome.kind {
+            Created(c) | Truncated(c) | Updated(c) | TruncatedUpdate(c) | Duplicate(c)
+            | Deleted(c) | Unmarked(c) => (Some(c), None, vec![]),
+            Labeled(l) | Unlabeled(l) => (None, Some(l), vec![]),
+            Faked | NotOwned | Locked | ArchivedRepo | NotFound | Closed => (None, None, vec![]),
+            LabelError(l, err) => (None, Some(l), err.chain().map(|e| e.to_string()).collect()),
+            Error(err) => (None, None, err.chain().map(|e| e.to_string()).collect()),
+        };
+        Self {
+            issue: outcome.issue.clone(),
+            moved_from: outcome.moved_from.clone(),
+            kind: outcome.kind.name().to_string(),
+            comment: comment.cloned(),
+            label: label.cloned(),
+            error,
+        }
+    }
+}
+
+/// The ledger file, to which entries are appended.
+#[derive(Clone, Debug)]
+pub struct Ledger {
+    path: String,
+}
+
+impl Ledger {
+    pub fn new(path: String) -> Self {
+        Self { path }
+    }
+
+    /// Append the given entry to the ledger (creating the file if needed).
+    pub fn append(&self, entry: &LedgerEntry) -> Result<(), LedgerError> {
+        let mut line = serde_json::to_string(entry).unwrap();
+        line.push('\n');
+        // a single write in append mode, so that concurrent runs do not interleave their entries
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|err| LedgerError(self.path.clone(), err))
+    }
+
+    /// Start recording the outcomes of a run.
+    /// The entry is appended to the ledger when the returned value is dropped.
+    pub fn start(&self, entry: LedgerEntry) -> PendingEntry<'_> {
+        PendingEntry {
+            ledger: self,
+            entry,
+        }
+    }
+}
+
+/// The entry of a run in progress, appended to the ledger when dropped
+/// (so that interrupted runs are recorded too).
+pub struct PendingEntry<'a> {
+    ledger: &'a Ledger,
+    entry: LedgerEntry,
+}
+
+impl PendingEntry<'_> {
+    pub fn push(&mut self, outcome: &Outcome) {
+        self.entry.outcomes.push(outcome.into());
+    }
+
+    pub fn complete(&mut self) {
+        self.entry.completed = true;
+    }
+}
+
+impl Drop for PendingEntry<'_> {
+    fn drop(&mut self) {
+        self.entry.ended = Utc::now();
+        if let Err(err) = self.ledger.append(&self.entry) {
+            log::error!(
+                "Could not record run {} in the ledger: {:?}",
+                self.entry.run_id,
+                anyhow::Error::new(err)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::outcome::{GITHUB_HOST, Issue};
+
+    fn entry(run_id: &str) -> LedgerEntry {
+        LedgerEntry {
+            run_id: run_id.into(),
+            started: Utc::now(),
+            ended: Utc::now(),
+            trigger: Trigger::new(Some("pchampin".into())),
+            channel: "#vc".into(),
+            date: NaiveDate::from_ymd_opt(2024, 11, 14).unwrap(),
+            groups: "wg/vc".into(),
+            minutes: "https://www.w3.org/2024/11/14-vc-minutes.html".into(),
+            dry_run: false,
+            completed: false,
+            outcomes: vec![],
+        }
+    }
+
+    #[test]
+    fn append() {
+        let path = std::env::temp_dir().join(format!("m2g-ledger-{}.jsonl", std::process::id()));
+        let ledger = Ledger::new(path.to_str().unwrap().into());
+        let issue = Issue::new(GITHUB_HOST, "w3c", "vc", 1);
+        let comment = "https://github.com/w3c/vc/issues/1#issuecomment-42";
+        {
+            let mut pending = ledger.start(entry("1"));
+            pending.push(&Outcome::created(issue.clone(), comment));
+            let err = anyhow::Error::msg("404 Not Found").context("Posting comment");
+            pending.push(&Outcome::error(issue, err));
+            pending.complete();
+        }
+        drop(ledger.start(entry("2")));
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let entries: Vec<LedgerEntry> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].completed);
+        assert!(!entries[1].completed);
+        assert_eq!(
+            entries[0].trigger,
+            Trigger::Irc {
+                nick: "pchampin".into()
+            }
+        );
+        assert_eq!(entries[0].outcomes[0].kind, "created");
+        assert_eq!(entries[0].outcomes[0].comment.as_deref(), Some(comment));
+        assert_eq!(
+            entries[0].outcomes[1].error,
+            vec!["Posting comment", "404 Not Found"]
+        );
+    }
+}
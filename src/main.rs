@@ -2,17 +2,29 @@ use anyhow::Result;
 use clap::Parser;
 
 mod args;
+mod discussions;
 mod engine;
 mod error;
+mod github;
 mod ircbot;
+mod ledger;
 mod manual;
+mod marker;
+mod minutes;
 mod outcome;
+mod participants;
 mod repositories;
+mod revert;
+mod runs;
+mod template;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = args::CmdArgs::parse();
-    let token = args.token;
+    if let Err(err) = args.validate() {
+        err.exit();
+    }
+    let github = args.github;
 
     env_logger::builder()
         .format_timestamp(None)
@@ -20,7 +32,8 @@ async fn main() -> Result<()> {
         .init();
 
     match args.subcommand {
-        args::SubCmdArgs::IrcBot(args) => ircbot::command(token, args).await,
-        args::SubCmdArgs::Manual(args) => manual::command(token, args).await,
+        args::SubCmdArgs::IrcBot(args) => ircbot::command(github, args).await,
+        args::SubCmdArgs::Manual(args) => manual::command(github, args).await,
+        args::SubCmdArgs::Revert(args) => revert::command(github, args).await,
     }
 }
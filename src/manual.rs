@@ -1,14 +1,272 @@
-use anyhow::Result;
-use futures::TryStreamExt;
+use std::sync::Arc;
 
-use crate::args::EngineArgs;
+use anyhow::{Error, Result};
+use async_stream::try_stream;
+use chrono::NaiveDate;
+use futures::{Stream, TryStreamExt};
+use serde::Deserialize;
 
-pub async fn command(token: String, args: EngineArgs) -> Result<()> {
-    let engine = crate::engine::Engine::new(token, args).await?;
-    engine.run().try_for_each_concurrent(None, noop).await?;
+use crate::args::{EngineArgs, GitHubArgs, ManualArgs};
+use crate::engine::{Backend, Engine, today};
+use crate::error::{
+    EngineCreationError::{self, MinutesNotFound},
+    ManifestError,
+};
+use crate::github::GitHub;
+use crate::outcome::Outcome;
+
+pub async fn command(github: GitHubArgs, args: ManualArgs) -> Result<()> {
+    if let Some(path) = &args.manifest {
+        let manifest = load_manifest(path)?;
+        let (processed, failed) = print_reports(batch(github, args.engine, manifest)).await?;
+        println!(
+            "{} meeting(s) processed, {failed} failed: {}",
+            processed.len(),
+            counts(processed.iter().flat_map(|m| &m.outcomes)),
+        );
+        return Ok(());
+    }
+    let Some(from) = args.from else {
+        let engine = Engine::new(github, args.engine).await?;
+        engine.run().try_for_each_concurrent(None, noop).await?;
+        return Ok(());
+    };
+    let to = args.to.unwrap_or_else(today);
+    let (processed, failed) = print_reports(backfill(github, args.engine, from, to)).await?;
+    println!(
+        "{} meeting(s) found from {from} to {to}, {failed} failed",
+        processed.len()
+    );
     Ok(())
 }
 
 async fn noop<T>(_: T) -> Result<()> {
     Ok(())
 }
+
+/// Print each report as it comes, and return the processed meetings and the number of failures.
+async fn print_reports(
+    reports: impl Stream<Item = Result<Report>>,
+) -> Result<(Vec<Meeting>, usize)> {
+    let (mut processed, mut failed) = (vec![], 0);
+    reports
+        .try_for_each(|report| {
+            match report {
+                Report::Processed(meeting) => {
+                    println!("{}", meeting.summary());
+                    processed.push(meeting);
+                }
+                Report::Failed(source, err) => {
+                    println!("{source}: failed ({err:#})");
+                    failed += 1;
+                }
+            }
+            noop(())
+        })
+        .await?;
+    Ok((processed, failed))
+}
+
+/// The outcomes of processing the minutes of one meeting.
+pub struct Meeting {
+    pub date: NaiveDate,
+    /// The URL of the minutes
+    pub minutes: String,
+    pub outcomes: Vec<Outcome>,
+}
+
+impl Meeting {
+    /// Run the given engine to completion.
+    async fn process(engine: Engine) -> Result<Self> {
+        log::info!("Processing minutes of {}: {}", engine.date(), engine.url());
+        let outcomes = engine.run().try_collect().await?;
+        Ok(Self {
+            date: engine.date(),
+            minutes: engine.url().to_string(),
+            outcomes,
+        })
+    }
+
+    /// A one-line summary, counting the outcomes of each kind.
+    pub fn summary(&self) -> String {
+        format!("{} {}: {}", self.date, self.minutes, counts(&self.outcomes))
+    }
+}
+
+/// The number of outcomes of each kind, e.g. `2 created, 1 not owned`.
+pub fn counts<'a>(outcomes: impl IntoIterator<Item = &'a Outcome>) -> String {
+    let mut counts: Vec<(&str, usize)> = vec![];
+    for outcome in outcomes {
+        let name = outcome.kind.name();
+        match counts.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((name, 1)),
+        }
+    }
+    if counts.is_empty() {
+        return "no issue in the (sub)topics".to_string();
+    }
+    counts
+        .iter()
+        .map(|(name, count)| format!("{count} {}", name.replace('_', " ")))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Process the minutes of every day from `from` to `to` (inclusive),
+/// skipping the days without minutes, and yield a report for each meeting.
+///
+/// A day whose minutes cannot be processed does not prevent the following days from being processed.
+/// All days share the same GitHub client and rate limiter.
+pub fn backfill(
+    github: GitHubArgs,
+    args: EngineArgs,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> impl Stream<Item = Result<Report>> {
+    try_stream! {
+        let backend = Backend::new(Arc::new(GitHub::new(&github)?), args.rate_limit);
+        for date in from.iter_days().take_while(|date| *date <= to) {
+            let args = EngineArgs {
+                date: Some(date),
+                ..args.clone()
+            };
+            match Engine::with_backend(github.clone(), args, backend.clone()).await {
+                Err(MinutesNotFound(url, _)) => log::debug!("No minutes found at {url}"),
+                engine => yield report(date.to_string(), engine).await,
+            }
+        }
+    }
+}
+
+/// An entry of a manifest (a JSON array of such entries), describing minutes to process in batch mode.
+///
+/// Fields that are not provided default to the corresponding command-line options.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestEntry {
+    /// URL to read minutes from
+    pub url: Option<String>,
+    /// File to read minutes from (the URL is still used for links)
+    pub file: Option<String>,
+    /// IRC channel from where the minutes were generated
+    pub channel: Option<String>,
+    /// Comma-separated list of groups concerned by these minutes
+    pub groups: Option<String>,
+    /// Date of the minutes (default: inferred from the URL)
+    pub date: Option<NaiveDate>,
+    /// Whether to include the transcript in GitHub comments
+    pub transcript: Option<bool>,
+    /// Repositories to consider as owned by the groups, in addition to those declared in w3c/groups
+    #[serde(default)]
+    pub extra_repositories: Vec<String>,
+}
+
+impl ManifestEntry {
+    /// The options of the engine for this entry, based on the given command-line options.
+    fn engine_args(&self, args: &EngineArgs) -> EngineArgs {
+        let mut args = args.clone();
+        if let Some(channel) = &self.channel {
+            args.channel = Some(channel.clone());
+        }
+        if let Some(groups) = &self.groups {
+            args.groups = Some(groups.clone());
+        }
+        if let Some(transcript) = self.transcript {
+            args.transcript = transcript;
+        }
+        args.url.clone_from(&self.url);
+        args.file.clone_from(&self.file);
+        args.date = self.date;
+        args.extra_repositories
+            .extend(self.extra_repositories.iter().cloned());
+        args
+    }
+
+    /// A short description of this entry, for reports.
+    fn source(&self) -> String {
+        match (&self.file, &self.url, self.date) {
+            (Some(file), _, _) => file.clone(),
+            (None, Some(url), _) => url.clone(),
+            (None, None, Some(date)) => format!("{date}"),
+            (None, None, None) => "today".to_string(),
+        }
+    }
+}
+
+pub fn load_manifest(path: &str) -> Result<Vec<ManifestEntry>, ManifestError> {
+    let json =
+        std::fs::read_to_string(path).map_err(|err| ManifestError::File(path.into(), err))?;
+    serde_json::from_str(&json).map_err(|err| ManifestError::Parse(path.into(), err))
+}
+
+/// The report of processing one entry of a manifest, or the minutes of one day.
+pub enum Report {
+    Processed(Meeting),
+    /// The minutes could not be processed (description of the entry or day, and error)
+    Failed(String, Error),
+}
+
+/// Process the minutes with the given engine (if it could be created), and report about it.
+async fn report(source: String, engine: Result<Engine, EngineCreationError>) -> Report {
+    let meeting = match engine {
+        Ok(engine) => Meeting::process(engine).await,
+        Err(err) => Err(Error::new(err)),
+    };
+    match meeting {
+        Ok(meeting) => Report::Processed(meeting),
+        Err(err) => {
+            log::error!("{:?}", err);
+            Report::Failed(source, err)
+        }
+    }
+}
+
+/// Process all the entries of a manifest, and yield a report for each of them.
+///
+/// An entry whose minutes cannot be processed (e.g. because no channel is given for it)
+/// does not prevent the others from being processed.
+/// All entries share the same GitHub client and rate limiter.
+pub fn batch(
+    github: GitHubArgs,
+    args: EngineArgs,
+    manifest: Vec<ManifestEntry>,
+) -> impl Stream<Item = Result<Report>> {
+    try_stream! {
+        let backend = Backend::new(Arc::new(GitHub::new(&github)?), args.rate_limit);
+        for entry in manifest {
+            let engine_args = entry.engine_args(&args);
+            let engine = Engine::with_backend(github.clone(), engine_args, backend.clone()).await;
+            yield report(entry.source(), engine).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::outcome::{GITHUB_HOST, Issue};
+
+    #[test]
+    fn summary() {
+        let issue = |id| Issue::new(GITHUB_HOST, "w3c", "vc", id);
+        let mut meeting = Meeting {
+            date: NaiveDate::from_ymd_opt(2024, 11, 14).unwrap(),
+            minutes: "https://www.w3.org/2024/11/14-vc-minutes.html".into(),
+            outcomes: vec![],
+        };
+        assert_eq!(
+            meeting.summary(),
+            "2024-11-14 https://www.w3.org/2024/11/14-vc-minutes.html: no issue in the (sub)topics"
+        );
+        meeting.outcomes = vec![
+            Outcome::created(issue(1), "c1"),
+            Outcome::not_owned(issue(2)),
+            Outcome::created(issue(3), "c3"),
+        ];
+        assert_eq!(
+            meeting.summary(),
+            "2024-11-14 https://www.w3.org/2024/11/14-vc-minutes.html: 2 created, 1 not owned"
+        );
+    }
+}
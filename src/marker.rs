@@ -0,0 +1,157 @@
+//! I define the hidden marker embedded in every comment posted by this program.
+
+use std::{fmt, sync::LazyLock};
+
+use chrono::NaiveDate;
+use regex::Regex;
+
+/// A machine-readable marker, embedded as an HTML comment in every comment posted by this program,
+/// identifying the part of the minutes the comment points to.
+///
+/// It is serialized as `<!-- minutes_to_gh minutes=... anchors=... date=... groups=... version=... -->`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Marker {
+    /// The URL of the minutes (without anchor)
+    pub minutes: String,
+    /// The anchors of the relevant sections in the minutes
+    pub anchors: Vec<String>,
+    /// The date of the meeting
+    pub date: NaiveDate,
+    /// The comma-separated list of groups concerned by the minutes
+    pub groups: String,
+    /// The version of this program that posted the comment
+    pub version: String,
+}
+
+impl Marker {
+    /// The version of this program, to be used in new markers.
+    pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+    /// Find and parse the (first) marker in the body of a comment, if any.
+    pub fn find(body: &str) -> Option<Self> {
+        static RE_MARKER: LazyLock<Regex> =
+            LazyLock::new(|| Regex::new(r"<!-- minutes_to_gh ((?:[a-z]+=\S* )+)-->").unwrap());
+        let fields = RE_MARKER.captures(body)?.get(1).unwrap().as_str();
+        let field = |name: &str| {
+            fields
+                .split_whitespace()
+                .filter_map(|f| f.split_once('='))
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| decode(value))
+        };
+        Some(Self {
+            minutes: field("minutes")?,
+            anchors: field("anchors")?
+                .split(',')
+                .filter(|a| !a.is_empty())
+                .map(ToString::to_string)
+                .collect(),
+            date: field("date")?.parse().ok()?,
+            groups: field("groups")?,
+            version: field("version")?,
+        })
+    }
+}
+
+impl fmt::Display for Marker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "<!-- minutes_to_gh minutes={} anchors={} date={} groups={} version={} -->",
+            no_space(&self.minutes),
+            no_space(&self.anchors.join(",")),
+            self.date,
+            no_space(&self.groups),
+            self.version,
+        )
+    }
+}
+
+/// Percent-encode whitespace, which would otherwise break the marker (and `--`, which would end the HTML comment).
+fn no_space(txt: &str) -> String {
+    txt.replace('%', "%25")
+        .replace(' ', "%20")
+        .replace('\t', "%09")
+        .replace('\n', "%0A")
+        .replace("--", "-%2D")
+}
+
+/// Reverse [`no_space`], decoding every percent-encoded character.
+fn decode(txt: &str) -> String {
+    let mut decoded = String::with_capacity(txt.len());
+    let mut rest = txt;
+    while let Some(i) = rest.find('%') {
+        decoded.push_str(&rest[..i]);
+        let code = rest
+            .get(i + 1..i + 3)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match code {
+            Some(byte) if byte.is_ascii() => {
+                decoded.push(byte as char);
+                rest = &rest[i + 3..];
+            }
+            _ => {
+                decoded.push('%');
+                rest = &rest[i + 1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use test_case::test_case;
+
+    fn marker() -> Marker {
+        Marker {
+            minutes: "https://www.w3.org/2024/11/14-vc-minutes.html".into(),
+            anchors: vec!["t04".into(), "t07".into()],
+            date: NaiveDate::from_ymd_opt(2024, 11, 14).unwrap(),
+            groups: "wg/vc,cg/credentials".into(),
+            version: "0.10.0".into(),
+        }
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            marker().to_string(),
+            "<!-- minutes_to_gh minutes=https://www.w3.org/2024/11/14-vc-minutes.html anchors=t04,t07 date=2024-11-14 groups=wg/vc,cg/credentials version=0.10.0 -->"
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let body = format!("This was discussed...\n\n{}\n", marker());
+        assert_eq!(Marker::find(&body), Some(marker()));
+    }
+
+    #[test_case("https://example.org/a%20b--c.html")]
+    #[test_case("https://example.org/100%25 done\t--\n--.html")]
+    #[test_case("https://example.org/%2D%")]
+    fn round_trip_encoded(minutes: &str) {
+        let marker = Marker {
+            minutes: minutes.into(),
+            groups: "wg/a b".into(),
+            ..marker()
+        };
+        let body = format!("This was discussed...\n\n{marker}\n");
+        assert_eq!(Marker::find(&body), Some(marker));
+    }
+
+    #[test_case("no marker")]
+    #[test_case("<!-- minutes_to_gh minutes=https://example.org/ anchors=t01 -->"; "missing fields")]
+    #[test_case("<!-- minutes_to_gh minutes=https://example.org/ anchors=t01 date=yesterday groups=wg/vc version=0.10.0 -->"; "invalid date")]
+    fn not_found(body: &str) {
+        assert_eq!(Marker::find(body), None);
+    }
+
+    #[test]
+    fn unknown_fields_ignored() {
+        let body = "<!-- minutes_to_gh minutes=https://www.w3.org/2024/11/14-vc-minutes.html anchors=t04,t07 date=2024-11-14 groups=wg/vc,cg/credentials future=42 version=0.10.0 -->";
+        assert_eq!(Marker::find(body), Some(marker()));
+    }
+}
@@ -0,0 +1,220 @@
+//! I define the [`MinutesFormat`] trait, abstracting over the different formats in which minutes are published.
+
+use std::sync::LazyLock;
+
+use clap::ValueEnum;
+use regex::Regex;
+
+use crate::outcome::{GITHUB_HOST, Issue};
+use crate::repositories::Repository;
+
+mod html;
+mod irclog;
+mod markdown;
+
+pub use html::HtmlMinutes;
+pub use irclog::IrcLogMinutes;
+pub use markdown::MarkdownMinutes;
+
+/// A parsed document containing the minutes of a meeting.
+pub trait MinutesFormat {
+    /// Iter over all github issues cited in a heading (or just after it),
+    /// together with the link to that heading (built from `url`),
+    /// and optionally (see [`ExtractionOptions`]) information about the part of the minutes where they are discussed.
+    fn issues_with_link<'a>(
+        &'a self,
+        url: &'a str,
+        options: &'a ExtractionOptions,
+    ) -> Box<dyn Iterator<Item = Mention> + 'a>;
+}
+
+/// A mention of an issue in a section of the minutes.
+#[derive(Clone, Debug)]
+pub struct Mention {
+    pub issue: Issue,
+    /// The link to the section of the minutes
+    pub link: String,
+    /// The title of the section of the minutes
+    pub title: String,
+    /// A markdown version of the section (empty unless [`ExtractionOptions::transcript`] is set)
+    pub transcript: String,
+    /// The resolutions and actions recorded in the section (empty unless [`ExtractionOptions::resolutions`] is set)
+    pub resolutions: Vec<String>,
+    /// The names (IRC nicks or W3C names) of the people who spoke in the section,
+    /// in order of appearance (empty unless [`ExtractionOptions::participants`] is set)
+    pub speakers: Vec<String>,
+}
+
+/// Options controlling how issues are extracted from the minutes.
+#[derive(Clone, Debug)]
+pub struct ExtractionOptions {
+    /// Whether to extract the transcript of each section (otherwise, it will be an empty string)
+    pub transcript: bool,
+    /// Whether to extract the resolutions and actions of each section (otherwise, there will be none)
+    pub resolutions: bool,
+    /// Whether to extract the speakers of each section (otherwise, there will be none)
+    pub participants: bool,
+    /// The repository against which bare `#123` references are resolved (if any)
+    pub default_repository: Option<Repository>,
+    /// How to locate the heading to link to (only relevant for HTML minutes)
+    pub heuristic: Heuristic,
+    /// The host of the GitHub web interface, against which links to issues are recognized
+    pub github_host: String,
+}
+
+impl Default for ExtractionOptions {
+    fn default() -> Self {
+        Self {
+            transcript: false,
+            resolutions: false,
+            participants: false,
+            default_repository: None,
+            heuristic: Heuristic::default(),
+            github_host: GITHUB_HOST.to_string(),
+        }
+    }
+}
+
+/// The different heuristics to locate the heading (and hence the fragment of the minutes)
+/// corresponding to an issue mentioned in HTML minutes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Heuristic {
+    /// The heading containing the mention
+    Ancestor,
+    /// The heading containing the mention, or preceding the mention or one of its ancestors
+    #[default]
+    Relevant,
+    /// The heading preceding the mention in document order, at any depth
+    Closest,
+}
+
+impl std::str::FromStr for Heuristic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, true)
+    }
+}
+
+/// The different formats of minutes supported by this crate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum MinutesFormatKind {
+    /// HTML minutes, as generated by RRSAgent / scribe.perl
+    Html,
+    /// Markdown minutes, as generated by scribejs
+    Markdown,
+    /// Raw IRC logs, as published by RRSAgent
+    IrcLog,
+}
+
+impl MinutesFormatKind {
+    /// Guess the format of some minutes, based on their location (URL or filename) and their content.
+    pub fn guess(location: &str, content: &str) -> Self {
+        static RE_LOG_LINE: LazyLock<Regex> =
+            LazyLock::new(|| Regex::new(r"^[0-9]{2}:[0-9]{2}:[0-9]{2} ").unwrap());
+        let path = location.split(['?', '#']).next().unwrap_or(location);
+        if path.ends_with(".txt") || RE_LOG_LINE.is_match(content) {
+            Self::IrcLog
+        } else if path.ends_with(".md") || path.ends_with(".markdown") {
+            Self::Markdown
+        } else if path.ends_with(".html")
+            || path.ends_with(".htm")
+            || content.trim_start().starts_with('<')
+        {
+            Self::Html
+        } else {
+            Self::Markdown
+        }
+    }
+
+    /// Parse `content` according to this format.
+    pub fn parse(self, content: &str) -> Box<dyn MinutesFormat> {
+        match self {
+            Self::Html => Box::new(HtmlMinutes::parse(content)),
+            Self::Markdown => Box::new(MarkdownMinutes::parse(content)),
+            Self::IrcLog => Box::new(IrcLogMinutes::parse(content)),
+        }
+    }
+}
+
+/// Recognize a resolution or an action in a line of text (IRC or markdown),
+/// and normalize it as `RESOLUTION: ...` or `ACTION: ...`.
+fn parse_resolution(line: &str) -> Option<String> {
+    static RE_RESOLUTION: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(
+            r"(?i)^[\s>*_]*(resolution|resolved|action)(?:\s*#[0-9]+)?\s*:[\s*_]*(.*?)[\s*_]*$",
+        )
+        .unwrap()
+    });
+    let c = RE_RESOLUTION.captures(line)?;
+    Some(format_resolution(
+        c.get(1).unwrap().as_str(),
+        c.get(2).unwrap().as_str(),
+    ))
+}
+
+/// Normalize the text of a resolution (if `kind` is "resolution" or "resolved") or an action (otherwise),
+/// making sure that it starts with `RESOLUTION:` or `ACTION:`, and that it contains no spurious @-mention.
+fn format_resolution(kind: &str, text: &str) -> String {
+    static RE_PREFIX: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"(?i)^(resolution|resolved|action)(\s*#[0-9]+)?\s*:\s*").unwrap()
+    });
+    let prefix = if kind.eq_ignore_ascii_case("action") {
+        "ACTION"
+    } else {
+        "RESOLUTION"
+    };
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let text = RE_PREFIX.replace(&text, "");
+    neutralize_mentions(&format!("{prefix}: {text}"))
+}
+
+/// Normalize the title of a section, collapsing whitespace and preventing spurious @-mentions.
+fn format_title(title: &str) -> String {
+    neutralize_mentions(&title.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Remove duplicates (ignoring case) from a list of speakers, keeping their order of appearance.
+fn dedup_speakers(speakers: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut ret: Vec<String> = vec![];
+    for speaker in speakers {
+        let speaker = speaker.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !speaker.is_empty() && !ret.iter().any(|s| s.eq_ignore_ascii_case(&speaker)) {
+            ret.push(speaker);
+        }
+    }
+    ret
+}
+
+/// Surround `@words` with `<code>` to prevent spurious @-mentions of github users.
+fn neutralize_mentions(txt: &str) -> String {
+    static AT_WORD: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"@[A-Za-z0-9_]+").unwrap());
+    AT_WORD.replace_all(txt, "<code>$0</code>").to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("https://www.w3.org/2024/11/14-vc-minutes.html", "" => MinutesFormatKind::Html)]
+    #[test_case("https://w3c.github.io/vc-wg/minutes/2024-11-14.md", "" => MinutesFormatKind::Markdown)]
+    #[test_case("minutes/2024-11-14.markdown", "<p>" => MinutesFormatKind::Markdown)]
+    #[test_case("https://example.org/minutes?date=2024-11-14", "<!DOCTYPE html>" => MinutesFormatKind::Html)]
+    #[test_case("https://example.org/minutes?date=2024-11-14", "# Minutes" => MinutesFormatKind::Markdown)]
+    #[test_case("https://www.w3.org/2024/11/14-vc-irc.txt", "" => MinutesFormatKind::IrcLog)]
+    #[test_case("https://example.org/log", "14:00:52 <RRSAgent> logging" => MinutesFormatKind::IrcLog)]
+    fn guess(location: &str, content: &str) -> MinutesFormatKind {
+        MinutesFormatKind::guess(location, content)
+    }
+
+    #[test_case("RESOLUTION: adopt PR 12" => Some("RESOLUTION: adopt PR 12".into()))]
+    #[test_case("resolved: adopt  PR 12" => Some("RESOLUTION: adopt PR 12".into()))]
+    #[test_case("> ***Resolution #1: adopt PR 12***" => Some("RESOLUTION: adopt PR 12".into()))]
+    #[test_case("ACTION: @manu to write the PR" => Some("ACTION: <code>@manu</code> to write the PR".into()))]
+    #[test_case("**Action #2:** manu to write the PR" => Some("ACTION: manu to write the PR".into()))]
+    #[test_case("no resolution: here" => None)]
+    fn parse_resolution(line: &str) -> Option<String> {
+        super::parse_resolution(line)
+    }
+}
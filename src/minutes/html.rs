@@ -0,0 +1,474 @@
+//! I implement [`MinutesFormat`] for HTML minutes, as generated by RRSAgent / scribe.perl.
+
+use std::{iter::once, sync::LazyLock};
+
+use ego_tree::NodeRef;
+use regex::Regex;
+use scraper::Node;
+use scraper::{CaseSensitivity, ElementRef, Html, Selector};
+
+use super::{
+    ExtractionOptions, Heuristic, Mention, MinutesFormat, dedup_speakers, format_resolution,
+    format_title,
+};
+use crate::outcome::Issue;
+
+mod gfm;
+
+/// HTML minutes, as generated by RRSAgent / scribe.perl.
+pub struct HtmlMinutes {
+    dom: Html,
+}
+
+impl HtmlMinutes {
+    pub fn parse(html: &str) -> Self {
+        Self {
+            dom: Html::parse_document(html),
+        }
+    }
+}
+
+impl MinutesFormat for HtmlMinutes {
+    fn issues_with_link<'a>(
+        &'a self,
+        url: &'a str,
+        options: &'a ExtractionOptions,
+    ) -> Box<dyn Iterator<Item = Mention> + 'a> {
+        Box::new(issues_with_link(&self.dom, url, options))
+    }
+}
+
+/// Iter over all github issues cited in an HTML heading,
+/// together with the link to that heading,
+/// and optionally (see below) a markdown version of the part of the minutes where they are discussed.
+///
+/// Issues can be cited as links, or as shorthands in the text (see [`Issue::find_shorthands`]).
+///
+/// The markdown fragment is only extracted if `options.transcript` is true,
+/// otherwise it will be an empty string.
+/// Likewise, resolutions and actions are only extracted if `options.resolutions` is true.
+fn issues_with_link<'a>(
+    dom: &'a Html,
+    url: &'a str,
+    options: &'a ExtractionOptions,
+) -> impl Iterator<Item = Mention> + 'a {
+    static SEL: LazyLock<Selector> = LazyLock::new(|| {
+        Selector::parse(r"h1[id] a, h2[id] a, h3[id] a, h4[id] a, h5[id] a, h6[id] a, h1[id] + * a, h2[id] + * a, h3[id] + * a, h4[id] + * a, h5[id] + * a, h6[id] + * a").unwrap()
+    });
+    static SEL_TEXT: LazyLock<Selector> = LazyLock::new(|| {
+        Selector::parse(r"h1[id], h2[id], h3[id], h4[id], h5[id], h6[id], h1[id] + *, h2[id] + *, h3[id] + *, h4[id] + *, h5[id] + *, h6[id] + *").unwrap()
+    });
+    let linked = dom
+        .select(&SEL)
+        .map(|a| {
+            (
+                a,
+                a.attr("href")
+                    .and_then(|href| Issue::try_from_url(href, &options.github_host)),
+            )
+        })
+        .filter_map(transpose_2nd);
+    let shorthands = dom.select(&SEL_TEXT).flat_map(move |e| {
+        unlinked_text(e).flat_map(move |txt| {
+            Issue::find_shorthands(
+                txt,
+                options.default_repository.as_ref(),
+                &options.github_host,
+            )
+            .map(move |issue| (e, issue))
+        })
+    });
+    linked
+        .chain(shorthands)
+        .map(move |(e, issue)| {
+            let fragment = find_hn_id(e, options.heuristic, options.transcript);
+            (issue, fragment)
+        })
+        .filter_map(transpose_2nd)
+        .map(move |(issue, fragment)| Mention {
+            issue,
+            link: format!("{}#{}", &url, fragment.id),
+            title: format_title(&fragment.heading.text().collect::<String>()),
+            resolutions: if options.resolutions {
+                extract_resolutions(fragment.heading)
+            } else {
+                vec![]
+            },
+            speakers: if options.participants {
+                extract_speakers(fragment.heading)
+            } else {
+                vec![]
+            },
+            transcript: fragment.content,
+        })
+}
+
+/// Iterate over all the text nodes in e, except those inside a link.
+fn unlinked_text(e: ElementRef<'_>) -> impl Iterator<Item = &str> {
+    e.descendants()
+        .filter(move |n| {
+            n.ancestors()
+                .take_while(|a| a.id() != e.id())
+                .filter_map(ElementRef::wrap)
+                .all(|a| a.value().name() != "a")
+        })
+        .filter_map(|n| n.value().as_text().map(|t| &**t))
+}
+
+/// Transpose a tuple on its 2nd component.
+fn transpose_2nd<T, U>(pair: (T, Option<U>)) -> Option<(T, U)> {
+    match pair {
+        (_, None) => None,
+        (a, Some(b)) => Some((a, b)),
+    }
+}
+
+/// Find the header (h1, h2...) corresponding to the given element, according to `heuristic`,
+/// and return the corresponding DocFragment.
+fn find_hn_id(e: ElementRef, heuristic: Heuristic, content: bool) -> Option<DocFragment> {
+    match heuristic {
+        Heuristic::Ancestor => find_ancestor_hn_id(e, content),
+        Heuristic::Relevant => find_relevant_hn_id(e, content),
+        Heuristic::Closest => find_closest_hn_id(e, content),
+    }
+}
+
+/// Find the header (h1, h2...) with an id which is the closest ancestor of the given element,
+/// and return the corresponding DocFragment.
+///
+/// Note that if content is false, the content field of the returned DocFragment will be an empty string.
+fn find_ancestor_hn_id(e: ElementRef, content: bool) -> Option<DocFragment> {
+    element_ancestors(e)
+        .filter_map(try_as_fragment_boundary)
+        .map(|(id, e)| {
+            if content {
+                extract_fragment(id, e)
+            } else {
+                DocFragment::dummy(id, e)
+            }
+        })
+        .next()
+}
+
+/// Find the header (h1, h2...) with an id which is the closest ancestor, or its predecessor, of the given element,
+/// and return the corresponding DocFragment.
+///
+/// Note that if content is false, the content field of the returned DocFragment will be an empty string.
+///
+/// This heuristics is somewhere in between [`find_ancestor_hn_id`] and [`find_closest_hn_id`].
+fn find_relevant_hn_id(e: ElementRef, content: bool) -> Option<DocFragment> {
+    element_ancestors(e)
+        .flat_map(element_prev_siblings)
+        .filter_map(try_as_fragment_boundary)
+        .map(|(id, e)| {
+            if content {
+                extract_fragment(id, e)
+            } else {
+                DocFragment::dummy(id, e)
+            }
+        })
+        .next()
+}
+
+/// Find the header (h1, h2...) with an id which is closest before the given element,
+/// and return the corresponding DocFragment.
+///
+/// Unlike [`find_relevant_hn_id`], this also considers headers nested in the preceding siblings
+/// of the element or its ancestors (e.g. the subtopic of a nested `section`).
+///
+/// Note that if content is false, the content field of the returned DocFragment will be an empty string.
+fn find_closest_hn_id(e: ElementRef, content: bool) -> Option<DocFragment> {
+    element_ancestors(e)
+        .flat_map(element_preceding)
+        .filter_map(try_as_fragment_boundary)
+        .map(|(id, e)| {
+            if content {
+                extract_fragment(id, e)
+            } else {
+                DocFragment::dummy(id, e)
+            }
+        })
+        .next()
+}
+
+/// If this element is a fragment boundary (i.e. a hn with and id),
+/// return its id and itself, otherwise, return None.
+fn try_as_fragment_boundary(e: ElementRef<'_>) -> Option<(&str, ElementRef<'_>)> {
+    static RE_HN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[hH][1234]$").unwrap());
+    let v = e.value();
+    if !RE_HN.is_match(v.name()) {
+        None
+    } else {
+        v.attr("id").map(|id| (id, e))
+    }
+}
+
+/// Extract and convert to markdown the fragment reachable from this element
+/// (see [`gfm::to_gfm`]).
+///
+/// # Precondition
+/// Element `e` must have an `id` attribute.
+fn extract_fragment<'a>(id: &'a str, e: ElementRef<'a>) -> DocFragment<'a> {
+    debug_assert!(e.value().attr("id") == Some(id));
+    let nodes = once(*e).chain(e.next_siblings().take_while(not_fragment_boundary));
+    DocFragment {
+        id,
+        heading: e,
+        content: gfm::to_gfm(nodes),
+    }
+}
+
+/// Extract the resolutions and actions (marked by scribe.perl with the `resolution` and `action` classes)
+/// in the fragment reachable from this element.
+fn extract_resolutions(e: ElementRef) -> Vec<String> {
+    static SEL: LazyLock<Selector> =
+        LazyLock::new(|| Selector::parse(".resolution, .action").unwrap());
+    e.next_siblings()
+        .take_while(not_fragment_boundary)
+        .filter_map(ElementRef::wrap)
+        .flat_map(|s| s.descendants().filter_map(ElementRef::wrap))
+        .filter(|r| SEL.matches(r))
+        // ignore nested markup (e.g. `<strong class="resolution">` in `<p class="resolution">`)
+        .filter(|r| {
+            !r.ancestors()
+                .filter_map(ElementRef::wrap)
+                .any(|a| SEL.matches(&a))
+        })
+        .map(|r| {
+            let kind = if r
+                .value()
+                .has_class("action", CaseSensitivity::AsciiCaseInsensitive)
+            {
+                "action"
+            } else {
+                "resolution"
+            };
+            format_resolution(kind, &r.text().collect::<String>())
+        })
+        .collect()
+}
+
+/// Extract the speakers (marked by scribe.perl with `cite` elements)
+/// in the fragment reachable from this element.
+fn extract_speakers(e: ElementRef) -> Vec<String> {
+    static SEL: LazyLock<Selector> = LazyLock::new(|| Selector::parse("cite").unwrap());
+    dedup_speakers(
+        e.next_siblings()
+            .take_while(not_fragment_boundary)
+            .filter_map(ElementRef::wrap)
+            .flat_map(|s| s.select(&SEL).chain(Some(s).filter(|s| SEL.matches(s))))
+            .map(|c| {
+                c.text()
+                    .collect::<String>()
+                    .trim_matches(|c: char| c == ':' || c == '<' || c == '>' || c.is_whitespace())
+                    .to_string()
+            }),
+    )
+}
+
+fn not_fragment_boundary(n: &NodeRef<Node>) -> bool {
+    let Some(e) = ElementRef::wrap(*n) else {
+        return true;
+    };
+    try_as_fragment_boundary(e).is_none()
+}
+
+/// Iterate over all ancestors of e that are elements.
+///
+/// "ancestors" here is to be understood in the broad sense:
+/// e is yielded as its first ancestor.
+fn element_ancestors(e: ElementRef) -> impl Iterator<Item = ElementRef> {
+    once(e).chain(e.ancestors().filter_map(ElementRef::wrap))
+}
+
+/// Iterate over all previous siblings of e that are elements.
+///
+/// "sibling" here is to be understood in the broad sense:
+/// e is yielded as its first sibling.
+fn element_prev_siblings(e: ElementRef) -> impl Iterator<Item = ElementRef> {
+    once(e).chain(e.prev_siblings().filter_map(ElementRef::wrap))
+}
+
+/// Iterate over e, then over its previous siblings and all their descendants, in reverse document order.
+///
+/// "sibling" here is to be understood in the broad sense:
+/// e is yielded first (but not its descendants).
+fn element_preceding(e: ElementRef) -> impl Iterator<Item = ElementRef> {
+    once(e).chain(e.prev_siblings().flat_map(|s| {
+        let mut subtree: Vec<_> = s.descendants().filter_map(ElementRef::wrap).collect();
+        subtree.reverse();
+        subtree
+    }))
+}
+
+/// Combines an ID from a DOM tree with the markdown version of the fragment "accessible" from this ID.
+struct DocFragment<'a> {
+    id: &'a str,
+    /// The element bearing the ID
+    heading: ElementRef<'a>,
+    content: String,
+}
+
+impl<'a> DocFragment<'a> {
+    fn dummy(id: &'a str, heading: ElementRef<'a>) -> Self {
+        Self {
+            id,
+            heading,
+            content: "".into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::repositories::Repository;
+    use test_case::test_case;
+
+    /// A corpus showing how the different heuristics behave,
+    /// with the structure of scribe.perl minutes (nested sections).
+    const MINUTES: &str = r#"<!DOCTYPE html>
+<html><body>
+<section>
+  <h2 id="t01">1. Status list <a href="https://github.com/w3c/vc/issues/1">vc#1</a></h2>
+  <p><a href="https://github.com/w3c/vc/issues/2">just after the topic</a></p>
+  <section>
+    <h3 id="t02">1.1 Subtopic</h3>
+    <p>Nothing here</p>
+  </section>
+  <p>After the subtopic: <a href="https://github.com/w3c/vc/issues/3">#3</a></p>
+  <p id="x01" class="phone"><cite>manu:</cite> let us adopt it</p>
+  <p id="x02" class="irc">&lt;<cite>ivan</cite>&gt; +1</p>
+  <p id="x03" class="phone"><cite>Manu:</cite> resolving</p>
+  <p id="r01" class="resolution"><strong class="resolution">RESOLUTION:</strong> adopt
+    the status list</p>
+  <p id="a01" class="action"><strong>ACTION:</strong> @manu to write the PR</p>
+</section>
+<section>
+  <h2 id="t03">2. Shorthands w3c/vc#4 and #5</h2>
+  <p>see also w3c/vc#6, but not <a href="https://example.org/">w3c/vc#7</a></p>
+</section>
+<section>
+  <h2 id="t04">3. Topic followed by a nested subtopic</h2>
+  <div>
+    <section><h3 id="t05">3.1 Subtopic</h3><p>Nothing here</p></section>
+    <p><a href="https://github.com/w3c/vc/issues/8">#8</a></p>
+  </div>
+</section>
+</body></html>
+"#;
+
+    fn issues(options: &ExtractionOptions) -> Vec<(String, String)> {
+        HtmlMinutes::parse(MINUTES)
+            .issues_with_link("https://example.org/minutes.html", options)
+            .map(|m| {
+                (
+                    m.issue.to_string(),
+                    m.link.split_once('#').unwrap().1.into(),
+                )
+            })
+            .collect()
+    }
+
+    #[test_case(Heuristic::Ancestor => vec![
+        ("w3c/vc#1".to_string(), "t01".to_string()),
+        ("w3c/vc#4".to_string(), "t03".to_string()),
+    ])]
+    #[test_case(Heuristic::Relevant => vec![
+        ("w3c/vc#1".to_string(), "t01".to_string()),
+        ("w3c/vc#2".to_string(), "t01".to_string()),
+        ("w3c/vc#8".to_string(), "t04".to_string()),
+        ("w3c/vc#4".to_string(), "t03".to_string()),
+        ("w3c/vc#6".to_string(), "t03".to_string()),
+    ])]
+    #[test_case(Heuristic::Closest => vec![
+        ("w3c/vc#1".to_string(), "t01".to_string()),
+        ("w3c/vc#2".to_string(), "t01".to_string()),
+        ("w3c/vc#8".to_string(), "t05".to_string()),
+        ("w3c/vc#4".to_string(), "t03".to_string()),
+        ("w3c/vc#6".to_string(), "t03".to_string()),
+    ])]
+    fn heuristic(heuristic: Heuristic) -> Vec<(String, String)> {
+        issues(&ExtractionOptions {
+            heuristic,
+            ..ExtractionOptions::default()
+        })
+    }
+
+    #[test_case(Heuristic::Ancestor => None)]
+    #[test_case(Heuristic::Relevant => Some("t01".to_string()))]
+    #[test_case(Heuristic::Closest => Some("t02".to_string()))]
+    fn heuristic_after_subtopic(heuristic: Heuristic) -> Option<String> {
+        static SEL: LazyLock<Selector> =
+            LazyLock::new(|| Selector::parse(r#"a[href$="/3"]"#).unwrap());
+        let minutes = HtmlMinutes::parse(MINUTES);
+        let a = minutes.dom.select(&SEL).next().unwrap();
+        find_hn_id(a, heuristic, false).map(|f| f.id.to_string())
+    }
+
+    #[test]
+    fn title() {
+        let minutes = HtmlMinutes::parse(MINUTES);
+        let got: Vec<_> = minutes
+            .issues_with_link(
+                "https://example.org/minutes.html",
+                &ExtractionOptions::default(),
+            )
+            .map(|m| m.title)
+            .collect();
+        assert_eq!(got[0], "1. Status list vc#1");
+        assert_eq!(got[2], "3. Topic followed by a nested subtopic");
+    }
+
+    #[test]
+    fn resolutions() {
+        let options = ExtractionOptions {
+            resolutions: true,
+            ..ExtractionOptions::default()
+        };
+        let minutes = HtmlMinutes::parse(MINUTES);
+        let got: Vec<_> = minutes
+            .issues_with_link("https://example.org/minutes.html", &options)
+            .map(|m| m.resolutions)
+            .collect();
+        assert_eq!(
+            got[0],
+            vec![
+                "RESOLUTION: adopt the status list".to_string(),
+                "ACTION: <code>@manu</code> to write the PR".to_string(),
+            ]
+        );
+        assert!(got[3].is_empty());
+    }
+
+    #[test]
+    fn speakers() {
+        let options = ExtractionOptions {
+            participants: true,
+            ..ExtractionOptions::default()
+        };
+        let minutes = HtmlMinutes::parse(MINUTES);
+        let got: Vec<_> = minutes
+            .issues_with_link("https://example.org/minutes.html", &options)
+            .map(|m| m.speakers)
+            .collect();
+        assert_eq!(got[0], vec!["manu".to_string(), "ivan".to_string()]);
+        assert!(got[3].is_empty());
+    }
+
+    #[test]
+    fn default_repository() {
+        let got = issues(&ExtractionOptions {
+            default_repository: Some(Repository::from("vc")),
+            ..ExtractionOptions::default()
+        });
+        assert_eq!(
+            got[3..],
+            [
+                ("w3c/vc#4".to_string(), "t03".to_string()),
+                ("w3c/vc#5".to_string(), "t03".to_string()),
+                ("w3c/vc#6".to_string(), "t03".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,344 @@
+//! I convert fragments of HTML minutes, as generated by scribe.perl, to GitHub-flavoured Markdown.
+
+use std::sync::LazyLock;
+
+use ego_tree::NodeRef;
+use regex::Regex;
+use scraper::{
+    CaseSensitivity, ElementRef, Node,
+    Node::{Element, Text},
+};
+
+use crate::minutes::neutralize_mentions;
+
+/// Convert a sequence of HTML nodes to GitHub-flavoured Markdown.
+///
+/// Speakers (`<cite>`) are in bold, IRC quotes (`p.irc`) are rendered as blockquotes,
+/// and resolutions and actions (`.resolution`, `.action`) are entirely in bold.
+/// `@words` are surrounded with `<code>` to prevent spurious @-mentions of github users.
+pub fn to_gfm<'a>(nodes: impl IntoIterator<Item = NodeRef<'a, Node>>) -> String {
+    let mut blocks = vec![];
+    push_blocks(nodes, &mut blocks);
+    blocks.join("\n\n")
+}
+
+/// Convert `nodes` to markdown blocks, grouping consecutive inline nodes into paragraphs.
+fn push_blocks<'a>(nodes: impl IntoIterator<Item = NodeRef<'a, Node>>, blocks: &mut Vec<String>) {
+    let mut inline = String::new();
+    for n in nodes {
+        match ElementRef::wrap(n) {
+            Some(e) if is_block(e) => {
+                push_paragraph(&std::mem::take(&mut inline), blocks);
+                push_block(e, blocks);
+            }
+            _ => push_inline(n, false, &mut inline),
+        }
+    }
+    push_paragraph(&inline, blocks);
+}
+
+fn push_block(e: ElementRef, blocks: &mut Vec<String>) {
+    let has_class = |c| {
+        e.value()
+            .has_class(c, CaseSensitivity::AsciiCaseInsensitive)
+    };
+    match e.value().name() {
+        name @ ("h1" | "h2" | "h3" | "h4" | "h5" | "h6") => {
+            let level = name[1..].parse().unwrap();
+            let title = lines(&inline(e, false)).join(" ");
+            blocks.push(format!("{} {title}", "#".repeat(level)));
+        }
+        "p" if has_class("resolution") || has_class("action") => {
+            let txt = paragraph(&inline(e, true));
+            if !txt.is_empty() {
+                blocks.push(format!("**{txt}**"));
+            }
+        }
+        "p" if has_class("irc") => {
+            let txt = paragraph(&inline(e, false));
+            if !txt.is_empty() {
+                blocks.push(quote(&txt));
+            }
+        }
+        "p" => push_paragraph(&inline(e, false), blocks),
+        "blockquote" => {
+            let mut inner = vec![];
+            push_blocks(e.children(), &mut inner);
+            if !inner.is_empty() {
+                blocks.push(quote(&inner.join("\n\n")));
+            }
+        }
+        list @ ("ul" | "ol") => {
+            let items: Vec<_> = e
+                .children()
+                .filter_map(ElementRef::wrap)
+                .filter(|li| li.value().name() == "li")
+                .enumerate()
+                .map(|(i, li)| {
+                    let marker = if list == "ol" {
+                        format!("{}. ", i + 1)
+                    } else {
+                        "* ".to_string()
+                    };
+                    let mut inner = vec![];
+                    push_blocks(li.children(), &mut inner);
+                    let indent = " ".repeat(marker.len());
+                    let content = inner
+                        .join("\n\n")
+                        .lines()
+                        .map(|l| {
+                            if l.is_empty() {
+                                String::new()
+                            } else {
+                                format!("{indent}{l}")
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    format!("{marker}{}", content.trim_start())
+                })
+                .collect();
+            if !items.is_empty() {
+                blocks.push(items.join("\n"));
+            }
+        }
+        "pre" => {
+            let code = e.text().collect::<String>();
+            let fence = if code.contains("```") { "~~~" } else { "```" };
+            blocks.push(format!("{fence}\n{}\n{fence}", code.trim_end()));
+        }
+        "hr" => blocks.push("---".to_string()),
+        _ => push_blocks(e.children(), blocks),
+    }
+}
+
+/// Convert the content of `e` to inline markdown.
+///
+/// Line breaks are represented by `\n`, and are only turned into markdown hard breaks by [`paragraph`].
+/// If `bold` is true, the content will be enclosed in bold markup, so nested bold markup is ignored.
+fn inline(e: ElementRef, bold: bool) -> String {
+    let mut out = String::new();
+    for n in e.children() {
+        push_inline(n, bold, &mut out);
+    }
+    out
+}
+
+fn push_inline(n: NodeRef<Node>, bold: bool, out: &mut String) {
+    match n.value() {
+        Text(txt) => {
+            static RE_SPACES: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\s+").unwrap());
+            out.push_str(&neutralize_mentions(&escape(
+                &RE_SPACES.replace_all(txt, " "),
+            )));
+        }
+        Element(_) => {
+            let e = ElementRef::wrap(n).unwrap();
+            match e.value().name() {
+                "br" => out.push('\n'),
+                "script" | "style" => {}
+                "strong" | "b" | "cite" if !bold => wrap("**", &inline(e, true), out),
+                "em" | "i" => wrap("*", &inline(e, bold), out),
+                "code" | "kbd" | "samp" | "tt" => {
+                    let code = e.text().collect::<String>();
+                    if code.contains('`') {
+                        wrap("``", &format!(" {code} "), out);
+                    } else {
+                        wrap("`", &code, out);
+                    }
+                }
+                "a" => match e.value().attr("href") {
+                    Some(href) => {
+                        let txt = inline(e, bold);
+                        let txt = txt.trim();
+                        let href = if href.contains([' ', '(', ')']) {
+                            format!("<{href}>")
+                        } else {
+                            href.to_string()
+                        };
+                        if txt.is_empty() {
+                            out.push_str(&format!("[{}]({href})", escape(&href)));
+                        } else {
+                            out.push_str(&format!("[{txt}]({href})"));
+                        }
+                    }
+                    None => out.push_str(&inline(e, bold)),
+                },
+                "img" => out.push_str(&neutralize_mentions(&escape(
+                    e.value().attr("alt").unwrap_or(""),
+                ))),
+                _ => out.push_str(&inline(e, bold)),
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Enclose `txt` in `delim`, keeping surrounding whitespace outside of it
+/// (as required by markdown for emphasis).
+fn wrap(delim: &str, txt: &str, out: &mut String) {
+    let trimmed = txt.trim();
+    if trimmed.is_empty() {
+        out.push_str(txt);
+        return;
+    }
+    let start = txt.len() - txt.trim_start().len();
+    let end = txt.trim_end().len();
+    out.push_str(&txt[..start]);
+    out.push_str(delim);
+    out.push_str(trimmed);
+    out.push_str(delim);
+    out.push_str(&txt[end..]);
+}
+
+/// Build a markdown paragraph from inline markdown (as produced by [`inline`]).
+fn paragraph(txt: &str) -> String {
+    // prevent lines from being interpreted as headings, lists, or setext underlines
+    static RE_BLOCK_START: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^(?:([#+=-])|([0-9]+)([.)]))").unwrap());
+    lines(txt)
+        .iter()
+        .map(|l| RE_BLOCK_START.replace(l, r"$2\$1$3"))
+        .collect::<Vec<_>>()
+        .join("\\\n")
+}
+
+/// Split inline markdown into non-empty lines, collapsing spaces.
+fn lines(txt: &str) -> Vec<String> {
+    static RE_SPACES: LazyLock<Regex> = LazyLock::new(|| Regex::new(r" +").unwrap());
+    RE_SPACES
+        .replace_all(txt, " ")
+        .split('\n')
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn push_paragraph(txt: &str, blocks: &mut Vec<String>) {
+    let p = paragraph(txt);
+    if !p.is_empty() {
+        blocks.push(p);
+    }
+}
+
+/// Turn markdown into a blockquote.
+fn quote(md: &str) -> String {
+    md.lines()
+        .map(|l| {
+            if l.is_empty() {
+                ">".into()
+            } else {
+                format!("> {l}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Escape characters from HTML text that would otherwise be interpreted as markdown or HTML.
+fn escape(txt: &str) -> String {
+    let mut out = String::with_capacity(txt.len());
+    for c in txt.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '\\' | '*' | '_' | '`' | '[' | ']' | '~' | '|' => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn is_block(e: ElementRef) -> bool {
+    matches!(
+        e.value().name(),
+        "address"
+            | "article"
+            | "aside"
+            | "blockquote"
+            | "dd"
+            | "details"
+            | "div"
+            | "dl"
+            | "dt"
+            | "figure"
+            | "footer"
+            | "h1"
+            | "h2"
+            | "h3"
+            | "h4"
+            | "h5"
+            | "h6"
+            | "header"
+            | "hr"
+            | "li"
+            | "main"
+            | "nav"
+            | "ol"
+            | "p"
+            | "pre"
+            | "section"
+            | "summary"
+            | "table"
+            | "td"
+            | "th"
+            | "tr"
+            | "ul"
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use scraper::Html;
+    use test_case::test_case;
+
+    fn convert(html: &str) -> String {
+        let dom = Html::parse_fragment(html);
+        to_gfm(dom.root_element().children())
+    }
+
+    #[test]
+    fn scribe_fragment() {
+        let html = r#"<h3 id="t01">1. Status list <a href="https://github.com/w3c/vc/issues/1">vc#1</a></h3>
+<p id="x01" class="phone"><cite>manu:</cite> we should
+  look at <a href="https://example.org/spec">the spec</a></p>
+<p id="x02" class="irc">&lt;<cite>ivan</cite>&gt; @manu, <em>agreed</em></p>
+<p id="r01" class="resolution"><strong class="resolution">RESOLUTION:</strong> adopt the status list</p>
+<p id="a01" class="action"><strong>ACTION:</strong> manu to write the PR</p>
+<ul><li>first</li><li>second<br>line</li></ul>"#;
+        assert_eq!(
+            convert(html),
+            "### 1. Status list [vc#1](https://github.com/w3c/vc/issues/1)
+
+**manu:** we should look at [the spec](https://example.org/spec)
+
+> &lt;**ivan**&gt; <code>@manu</code>, *agreed*
+
+**RESOLUTION: adopt the status list**
+
+**ACTION: manu to write the PR**
+
+* first
+* second\\
+  line"
+        );
+    }
+
+    #[test_case("<p>a *star* and [brackets]</p>" => r"a \*star\* and \[brackets\]")]
+    #[test_case("<p>an _emphasis_ and snake_case</p>" => r"an \_emphasis\_ and snake\_case")]
+    #[test_case("<p>see <img src=\"x.png\" alt=\"@someone\"></p>" => "see <code>@someone</code>")]
+    #[test_case("<p># not a heading</p>" => r"\# not a heading")]
+    #[test_case("<p>1. not a list</p>" => r"1\. not a list")]
+    #[test_case("<p>a <code>@code</code></p>" => "a `@code`")]
+    #[test_case("<p><a href=\"https://example.org/a (b)\">x</a></p>" => "[x](<https://example.org/a (b)>)")]
+    #[test_case("<pre>  indented\ncode</pre>" => "```\n  indented\ncode\n```")]
+    fn escaping(html: &str) -> String {
+        convert(html)
+    }
+}
@@ -0,0 +1,280 @@
+//! I implement [`MinutesFormat`] for raw IRC logs, as published by RRSAgent (`.txt` files).
+
+use std::{iter::once, sync::LazyLock};
+
+use regex::Regex;
+
+use super::{
+    ExtractionOptions, Mention, MinutesFormat, dedup_speakers, format_title, neutralize_mentions,
+    parse_resolution,
+};
+use crate::outcome::Issue;
+
+/// Raw IRC logs, as published by RRSAgent.
+///
+/// Topics and subtopics are rebuilt from `topic:` and `subtopic:` lines.
+pub struct IrcLogMinutes {
+    messages: Vec<Message>,
+    /// Indexes (in `messages`) of the `topic:` and `subtopic:` lines
+    topics: Vec<usize>,
+}
+
+/// A line of the log, uttered by someone on the channel.
+struct Message {
+    /// The timestamp of the line, formatted as `HH:MM:SS`
+    time: String,
+    nick: String,
+    text: String,
+    /// If this message is a `topic:` or `subtopic:` line, its depth (0 or 1) and title
+    topic: Option<(usize, String)>,
+}
+
+impl IrcLogMinutes {
+    pub fn parse(log: &str) -> Self {
+        static RE_MSG: LazyLock<Regex> = LazyLock::new(|| {
+            Regex::new(r"^([0-9]{2}:[0-9]{2}:[0-9]{2}) <([^>]+)> ?(.*)$").unwrap()
+        });
+        static RE_TOPIC: LazyLock<Regex> =
+            LazyLock::new(|| Regex::new(r"(?i)^\s*(sub)?topic\s*:\s*(.*?)\s*$").unwrap());
+        let messages: Vec<_> = log
+            .lines()
+            .filter_map(|line| RE_MSG.captures(line.trim_end()))
+            .map(|c| {
+                let text = c.get(3).unwrap().as_str().to_string();
+                let topic = RE_TOPIC.captures(&text).map(|t| {
+                    let depth = if t.get(1).is_some() { 1 } else { 0 };
+                    (depth, t.get(2).unwrap().as_str().to_string())
+                });
+                Message {
+                    time: c.get(1).unwrap().as_str().to_string(),
+                    nick: c.get(2).unwrap().as_str().to_string(),
+                    text,
+                    topic,
+                }
+            })
+            .collect();
+        let topics = messages
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| m.topic.is_some())
+            .map(|(i, _)| i)
+            .collect();
+        Self { messages, topics }
+    }
+
+    /// Extract and convert to markdown the messages from the topic line at index `i`
+    /// up to the next `topic:` or `subtopic:` line.
+    ///
+    /// Note also `@words` are surrounded with `<code>` to prevent spurious @-mentions of github users.
+    fn extract_fragment(&self, i: usize) -> String {
+        let (depth, title) = self.messages[i].topic.as_ref().unwrap();
+        let heading = format!("{} {}", "#".repeat(3 + depth), escape(title));
+        let lines = self.messages[i + 1..]
+            .iter()
+            .take_while(|m| m.topic.is_none())
+            .map(|m| format!("**&lt;{}&gt;** {}", m.nick, escape(&m.text)));
+        let md = once(heading).chain(lines).collect::<Vec<_>>().join("\n\n");
+        neutralize_mentions(&md)
+    }
+
+    /// Extract the speakers from the topic line at index `i` up to the next `topic:` or `subtopic:` line.
+    ///
+    /// Lines written by the scribe on behalf of someone else (`name: text`) are attributed to that person,
+    /// other lines to their author (except for bots).
+    fn extract_speakers(&self, i: usize) -> Vec<String> {
+        static RE_SCRIBED: LazyLock<Regex> =
+            LazyLock::new(|| Regex::new(r"^([A-Za-z][A-Za-z0-9_.-]*)\s*:\s").unwrap());
+        const NOT_SPEAKERS: [&str; 12] = [
+            "agenda",
+            "chair",
+            "chairs",
+            "date",
+            "meeting",
+            "present",
+            "regrets",
+            "scribe",
+            "scribenick",
+            "rrsagent",
+            "zakim",
+            "note",
+        ];
+        dedup_speakers(
+            self.messages[i + 1..]
+                .iter()
+                .take_while(|m| m.topic.is_none())
+                .filter(|m| parse_resolution(&m.text).is_none())
+                .map(|m| match RE_SCRIBED.captures(&m.text) {
+                    Some(c) => c[1].to_string(),
+                    None => m.nick.clone(),
+                })
+                .filter(|s| !NOT_SPEAKERS.iter().any(|n| n.eq_ignore_ascii_case(s))),
+        )
+    }
+}
+
+impl MinutesFormat for IrcLogMinutes {
+    fn issues_with_link<'a>(
+        &'a self,
+        url: &'a str,
+        options: &'a ExtractionOptions,
+    ) -> Box<dyn Iterator<Item = Mention> + 'a> {
+        // link to the HTML version of the log, rather than to the text file
+        let base = url.strip_suffix(".txt").unwrap_or(url);
+        Box::new(self.topics.iter().flat_map(move |&i| {
+            let topic = &self.messages[i];
+            let link = format!("{base}#T{}", topic.time.replace(':', "-"));
+            let transcript = if options.transcript {
+                self.extract_fragment(i)
+            } else {
+                String::new()
+            };
+            let resolutions = if options.resolutions {
+                self.messages[i + 1..]
+                    .iter()
+                    .take_while(|m| m.topic.is_none())
+                    .filter_map(|m| parse_resolution(&m.text))
+                    .collect()
+            } else {
+                vec![]
+            };
+            let speakers = if options.participants {
+                self.extract_speakers(i)
+            } else {
+                vec![]
+            };
+            let next = self.messages.get(i + 1).filter(|m| m.topic.is_none());
+            once(topic)
+                .chain(next)
+                .flat_map(|m| {
+                    find_urls(&m.text)
+                        .filter_map(|url| Issue::try_from_url(url, &options.github_host))
+                        .chain(Issue::find_shorthands(
+                            &m.text,
+                            options.default_repository.as_ref(),
+                            &options.github_host,
+                        ))
+                })
+                .map(move |issue| Mention {
+                    issue,
+                    link: link.clone(),
+                    title: format_title(&topic.topic.as_ref().unwrap().1),
+                    transcript: transcript.clone(),
+                    resolutions: resolutions.clone(),
+                    speakers: speakers.clone(),
+                })
+        }))
+    }
+}
+
+/// Iter over all URLs in an IRC message.
+fn find_urls(txt: &str) -> impl Iterator<Item = &str> {
+    static RE_URL: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r#"https?://[^\s<>"']+"#).unwrap());
+    RE_URL
+        .find_iter(txt)
+        .map(|m| m.as_str().trim_end_matches(['.', ',', ';', ':', ')']))
+}
+
+/// Prevent IRC text from being interpreted as HTML by GitHub.
+fn escape(txt: &str) -> String {
+    txt.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const LOG: &str = "\
+14:00:52 RRSAgent has joined #vcwg
+14:00:56 <RRSAgent> logging to https://www.w3.org/2024/11/14-vcwg-irc
+14:01:02 <manu> topic: Introductions
+14:01:10 <manu> @ivan, welcome
+14:02:00 <manu> Topic: Status list
+14:02:05 <manu> subtopic: https://github.com/w3c/vc-data-model/issues/1234
+14:02:10 <ivan> see also https://github.com/w3c/vc-data-model/pull/1235.
+14:02:20 <ivan> and https://github.com/w3c/vc-data-model/issues/99 (not right after the topic)
+14:02:30 <manu> RESOLVED: adopt the status list
+14:02:35 <manu> action: ivan to review
+14:03:00 <manu> topic: wrap up
+14:03:05 <manu> subtopic: nothing here
+14:03:10 <manu> https://github.com/w3c/vc-data-model/issues/1 is after a subtopic, not the topic
+";
+
+    #[test]
+    fn issues_with_link() {
+        let minutes = IrcLogMinutes::parse(LOG);
+        let got: Vec<_> = minutes
+            .issues_with_link(
+                "https://www.w3.org/2024/11/14-vcwg-irc.txt",
+                &ExtractionOptions::default(),
+            )
+            .map(|m| (m.issue.to_string(), m.link))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                (
+                    "w3c/vc-data-model#1234".to_string(),
+                    "https://www.w3.org/2024/11/14-vcwg-irc#T14-02-05".to_string()
+                ),
+                (
+                    "w3c/vc-data-model#1235".to_string(),
+                    "https://www.w3.org/2024/11/14-vcwg-irc#T14-02-05".to_string()
+                ),
+                (
+                    "w3c/vc-data-model#1".to_string(),
+                    "https://www.w3.org/2024/11/14-vcwg-irc#T14-03-05".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn resolutions() {
+        let minutes = IrcLogMinutes::parse(LOG);
+        let options = ExtractionOptions {
+            resolutions: true,
+            ..ExtractionOptions::default()
+        };
+        let got: Vec<_> = minutes
+            .issues_with_link("https://www.w3.org/2024/11/14-vcwg-irc.txt", &options)
+            .map(|m| m.resolutions)
+            .collect();
+        assert_eq!(
+            got[0],
+            vec![
+                "RESOLUTION: adopt the status list",
+                "ACTION: ivan to review"
+            ]
+        );
+        assert!(got[2].is_empty());
+    }
+
+    #[test]
+    fn speakers() {
+        let minutes = IrcLogMinutes::parse(LOG);
+        assert_eq!(minutes.extract_speakers(minutes.topics[0]), vec!["manu"]);
+        assert_eq!(
+            minutes.extract_speakers(minutes.topics[2]),
+            vec!["ivan".to_string()]
+        );
+        let log = "14:01:02 <manu> topic: Introductions\n14:01:03 <pchampin> scribe: pchampin\n14:01:10 <pchampin> ivan: hello\n14:01:20 <manu> welcome\n";
+        let minutes = IrcLogMinutes::parse(log);
+        assert_eq!(minutes.extract_speakers(0), vec!["ivan", "manu"]);
+    }
+
+    #[test]
+    fn transcript() {
+        let minutes = IrcLogMinutes::parse(LOG);
+        assert_eq!(
+            minutes.extract_fragment(minutes.topics[0]),
+            "### Introductions\n\n**&lt;manu&gt;** <code>@ivan</code>, welcome"
+        );
+        let fragment = minutes.extract_fragment(minutes.topics[2]);
+        assert!(fragment.starts_with("#### https://github.com/w3c/vc-data-model/issues/1234"));
+        assert!(fragment.contains("(not right after the topic)"));
+        assert!(!fragment.contains("wrap up"));
+    }
+}
@@ -0,0 +1,418 @@
+//! I implement [`MinutesFormat`] for Markdown minutes, as generated by [scribejs](https://github.com/w3c/scribejs).
+
+use std::{collections::HashMap, ops::Range, sync::LazyLock};
+
+use regex::Regex;
+
+use super::{
+    ExtractionOptions, Mention, MinutesFormat, dedup_speakers, format_title, neutralize_mentions,
+    parse_resolution,
+};
+use crate::outcome::Issue;
+
+/// Markdown minutes, as generated by scribejs.
+pub struct MarkdownMinutes {
+    text: String,
+    sections: Vec<Section>,
+}
+
+/// A part of the minutes starting with a heading (H1 to H4).
+struct Section {
+    /// The anchor of the heading
+    id: String,
+    /// The title of the heading
+    title: String,
+    /// The heading line
+    heading: Range<usize>,
+    /// The first block (paragraph, list...) after the heading
+    next_block: Range<usize>,
+    /// The whole section, heading included
+    content: Range<usize>,
+}
+
+impl MarkdownMinutes {
+    pub fn parse(markdown: &str) -> Self {
+        let text = markdown.to_string();
+        let lines = lines_with_range(&text);
+        let mut sections: Vec<Section> = vec![];
+        let mut slugs = HashMap::new();
+        let mut in_code = false;
+        for (i, (line, range)) in lines.iter().enumerate() {
+            if is_fence(line) {
+                in_code = !in_code;
+                continue;
+            }
+            if in_code {
+                continue;
+            }
+            let Some(title) = heading_title(line) else {
+                continue;
+            };
+            if let Some(previous) = sections.last_mut() {
+                previous.content.end = range.start;
+            }
+            let mut following = lines[i + 1..]
+                .iter()
+                .skip_while(|(l, _)| l.trim().is_empty())
+                .peekable();
+            let mut id = heading_id(line);
+            while let Some((l, _)) = following.next_if(|(l, _)| is_attribute_line(l)) {
+                id = id.or_else(|| heading_id(l));
+            }
+            let id = id.unwrap_or_else(|| {
+                let slug = slugify(title);
+                let count = slugs.entry(slug.clone()).or_insert(0);
+                *count += 1;
+                if *count == 1 {
+                    slug
+                } else {
+                    format!("{slug}-{}", *count - 1)
+                }
+            });
+            let block: Vec<_> = following
+                .skip_while(|(l, _)| l.trim().is_empty())
+                .take_while(|(l, _)| !l.trim().is_empty() && heading_title(l).is_none())
+                .map(|(_, r)| r.clone())
+                .collect();
+            let next_block = match (block.first(), block.last()) {
+                (Some(first), Some(last)) => first.start..last.end,
+                _ => range.end..range.end,
+            };
+            sections.push(Section {
+                id,
+                title: format_title(&RE_LINK.replace_all(title, "$1")),
+                heading: range.clone(),
+                next_block,
+                content: range.start..text.len(),
+            });
+        }
+        Self { text, sections }
+    }
+}
+
+impl MinutesFormat for MarkdownMinutes {
+    fn issues_with_link<'a>(
+        &'a self,
+        url: &'a str,
+        options: &'a ExtractionOptions,
+    ) -> Box<dyn Iterator<Item = Mention> + 'a> {
+        Box::new(self.sections.iter().flat_map(move |section| {
+            let heading = &self.text[section.heading.clone()];
+            let next_block = &self.text[section.next_block.clone()];
+            let transcript = if options.transcript {
+                self.extract_fragment(section)
+            } else {
+                String::new()
+            };
+            let resolutions = if options.resolutions {
+                self.text[section.content.clone()]
+                    .lines()
+                    .filter_map(parse_resolution)
+                    .collect()
+            } else {
+                vec![]
+            };
+            let speakers = if options.participants {
+                extract_speakers(&self.text[section.content.clone()])
+            } else {
+                vec![]
+            };
+            find_issues(heading, options)
+                .into_iter()
+                .chain(find_issues(next_block, options))
+                .map(move |issue| Mention {
+                    issue,
+                    link: format!("{url}#{}", section.id),
+                    title: section.title.clone(),
+                    transcript: transcript.clone(),
+                    resolutions: resolutions.clone(),
+                    speakers: speakers.clone(),
+                })
+        }))
+    }
+}
+
+impl MarkdownMinutes {
+    /// Extract the markdown of the given section, without the kramdown attribute lists.
+    ///
+    /// Note also `@words` are surrounded with `<code>` to prevent spurious @-mentions of github users.
+    fn extract_fragment(&self, section: &Section) -> String {
+        let md = self.text[section.content.clone()]
+            .lines()
+            .filter(|l| !is_attribute_line(l))
+            .collect::<Vec<_>>()
+            .join("\n");
+        neutralize_mentions(md.trim_end())
+    }
+}
+
+/// Extract the speakers of a markdown section, i.e. the bold names starting paragraphs
+/// (e.g. `**Ivan Herman:** ...`).
+fn extract_speakers(md: &str) -> Vec<String> {
+    static RE_SPEAKER: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^\s*(?:[*-]\s+)?\*\*([^*:]+):\*\*").unwrap());
+    dedup_speakers(
+        md.lines()
+            .filter_map(|l| RE_SPEAKER.captures(l))
+            .map(|c| c[1].to_string()),
+    )
+}
+
+/// Split `text` in lines, together with their byte range (not including the line terminator).
+fn lines_with_range(text: &str) -> Vec<(&str, Range<usize>)> {
+    let mut start = 0;
+    text.split_inclusive('\n')
+        .map(|l| {
+            let range = start..start + l.trim_end_matches(['\n', '\r']).len();
+            start += l.len();
+            (&text[range.clone()], range)
+        })
+        .collect()
+}
+
+fn is_fence(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("```") || line.starts_with("~~~")
+}
+
+/// If `line` is a heading of level 1 to 4, return its title.
+fn heading_title(line: &str) -> Option<&str> {
+    static RE_HN: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^ {0,3}#{1,4}[ \t]+(.*?)[ \t#]*$").unwrap());
+    let title = RE_HN.captures(line)?.get(1).unwrap().as_str();
+    let end = RE_ID_ATTR.find(title).map_or(title.len(), |m| m.start());
+    Some(&title[..end])
+}
+
+/// Whether this line is a kramdown attribute list (e.g. `{: #section1 .no_toc}`)
+/// or an HTML anchor (e.g. `<a name="section1"></a>`) attached to the preceding heading.
+fn is_attribute_line(line: &str) -> bool {
+    static RE_ATTR: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r#"^\s*(\{:[^}]*\}|<a\s+(name|id)\s*=\s*"[^"]*"\s*>\s*</a>)\s*$"#).unwrap()
+    });
+    RE_ATTR.is_match(line)
+}
+
+/// Find an explicit id in the given line, in one of the forms that scribejs or kramdown use.
+fn heading_id(line: &str) -> Option<String> {
+    static RE_ANCHOR: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r#"<a\s+(?:name|id)\s*=\s*"([^"]+)""#).unwrap());
+    RE_ID_ATTR
+        .captures(line)
+        .or_else(|| RE_ANCHOR.captures(line))
+        .map(|c| c.get(1).unwrap().as_str().to_string())
+}
+
+/// A kramdown attribute list containing an id, e.g. `{#section1}` or `{: #section1 .no_toc}`.
+static RE_ID_ATTR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[ \t]*\{:?[^}#]*#([^\s}]+)[^}]*\}[ \t]*$").unwrap());
+
+/// Compute the anchor that GitHub generates for a heading when rendering a Markdown file in a repository.
+///
+/// This differs from the anchors generated by kramdown (used by GitHub Pages),
+/// which e.g. strips leading digits and punctuation;
+/// minutes published with GitHub Pages must therefore use explicit anchors (e.g. `{: #section1}`).
+fn slugify(title: &str) -> String {
+    RE_LINK
+        .replace_all(title, "$1")
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// A markdown link or image, capturing its text.
+static RE_LINK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"!?\[([^\]]*)\]\([^)]*\)").unwrap());
+
+/// Find all issues referenced in a markdown text,
+/// either as URLs or as shorthands (outside the text of links).
+fn find_issues(txt: &str, options: &ExtractionOptions) -> Vec<Issue> {
+    let unlinked = RE_LINK.replace_all(txt, " ");
+    find_urls(txt)
+        .filter_map(|url| Issue::try_from_url(url, &options.github_host))
+        .chain(Issue::find_shorthands(
+            &unlinked,
+            options.default_repository.as_ref(),
+            &options.github_host,
+        ))
+        .collect()
+}
+
+/// Iter over all URLs in a markdown text,
+/// whether they appear in markdown links, in autolinks, or as is.
+fn find_urls(txt: &str) -> impl Iterator<Item = &str> {
+    static RE_URL: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r#"https?://[^\s()<>\[\]"']+"#).unwrap());
+    RE_URL
+        .find_iter(txt)
+        .map(|m| m.as_str().trim_end_matches(['.', ',', ';', ':']))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::repositories::Repository;
+    use test_case::test_case;
+
+    const MINUTES: &str = r#"# VC WG Telco — Minutes
+{: .no_toc}
+
+**Date:** 2024-11-14
+
+---
+
+### 1. Introductions
+{: #section1}
+
+**Manu Sporny:** welcome @everyone
+
+### 2. Issue processing
+{: #section2}
+
+#### 2.1 Status list (issue vc-data-model#1234)
+{: #section2-1}
+
+See [github issue #1234](https://github.com/w3c/vc-data-model/issues/1234).
+
+**Ivan Herman:** not in the next block https://github.com/w3c/vc-data-model/issues/99
+
+> ***Resolution #1: adopt the status list***
+
+#### 2.2 Better context <https://github.com/w3c/vc-data-model/pull/1235>
+
+```
+### not a heading https://github.com/w3c/vc-data-model/issues/1
+```
+
+### 3. https://github.com/w3c/vc-data-model/issues/1236 {#section3}
+
+### 4. Shorthands
+{: #section4}
+
+About w3c/vc-data-model#1237 and #1238, not [#1239](https://example.org/1239).
+"#;
+
+    fn issues(transcript: bool) -> Vec<(String, String, String)> {
+        let minutes = MarkdownMinutes::parse(MINUTES);
+        let options = ExtractionOptions {
+            transcript,
+            ..ExtractionOptions::default()
+        };
+        minutes
+            .issues_with_link("https://example.org/minutes.html", &options)
+            .map(|m| (m.issue.to_string(), m.link, m.transcript))
+            .collect()
+    }
+
+    #[test]
+    fn issues_with_link() {
+        let got: Vec<_> = issues(false)
+            .into_iter()
+            .map(|(issue, link, _)| (issue, link))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                (
+                    "w3c/vc-data-model#1234".to_string(),
+                    "https://example.org/minutes.html#section2-1".to_string()
+                ),
+                (
+                    "w3c/vc-data-model#1235".to_string(),
+                    "https://example.org/minutes.html#22-better-context-httpsgithubcomw3cvc-data-modelpull1235".to_string()
+                ),
+                (
+                    "w3c/vc-data-model#1236".to_string(),
+                    "https://example.org/minutes.html#section3".to_string()
+                ),
+                (
+                    "w3c/vc-data-model#1237".to_string(),
+                    "https://example.org/minutes.html#section4".to_string()
+                ),
+            ]
+        );
+    }
+
+    // as rendered in a GitHub repository (kramdown would give `better-context` for the first one)
+    #[test_case("2.2 Better context" => "22-better-context")]
+    #[test_case("Status_list: [#12](https://example.org/12)" => "status_list-12")]
+    fn slugify(title: &str) -> String {
+        super::slugify(title)
+    }
+
+    #[test]
+    fn default_repository() {
+        let minutes = MarkdownMinutes::parse(MINUTES);
+        let options = ExtractionOptions {
+            default_repository: Some(Repository::from("vc-data-model")),
+            ..ExtractionOptions::default()
+        };
+        let got: Vec<_> = minutes
+            .issues_with_link("https://example.org/minutes.html", &options)
+            .map(|m| m.issue.to_string())
+            .skip(3)
+            .collect();
+        assert_eq!(
+            got,
+            vec!["w3c/vc-data-model#1237", "w3c/vc-data-model#1238"]
+        );
+    }
+
+    #[test]
+    fn transcript() {
+        let got = issues(true);
+        assert!(got[0].2.starts_with("#### 2.1 Status list"));
+        assert!(!got[0].2.contains("{: #section2-1}"));
+        assert!(got[0].2.contains("Ivan Herman"));
+        assert!(!got[0].2.contains("2.2"));
+        assert!(got[1].2.contains("### not a heading"));
+    }
+
+    #[test]
+    fn title() {
+        let minutes = MarkdownMinutes::parse(MINUTES);
+        let titles: Vec<_> = minutes.sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles[3], "2.1 Status list (issue vc-data-model#1234)");
+        assert_eq!(titles[6], "4. Shorthands");
+    }
+
+    #[test]
+    fn resolutions() {
+        let minutes = MarkdownMinutes::parse(MINUTES);
+        let options = ExtractionOptions {
+            resolutions: true,
+            ..ExtractionOptions::default()
+        };
+        let got: Vec<_> = minutes
+            .issues_with_link("https://example.org/minutes.html", &options)
+            .map(|m| m.resolutions)
+            .collect();
+        assert_eq!(got[0], vec!["RESOLUTION: adopt the status list"]);
+        assert!(got[1].is_empty());
+    }
+
+    #[test]
+    fn speakers() {
+        let minutes = MarkdownMinutes::parse(MINUTES);
+        assert_eq!(
+            extract_speakers(&minutes.text[minutes.sections[3].content.clone()]),
+            vec!["Ivan Herman"]
+        );
+        assert_eq!(
+            extract_speakers("**Manu Sporny:** a\n\n* **manu  sporny:** b\n\n**Not a speaker**"),
+            vec!["Manu Sporny"]
+        );
+    }
+
+    #[test]
+    fn mentions_neutralized() {
+        let minutes = MarkdownMinutes::parse(MINUTES);
+        let fragment = minutes.extract_fragment(&minutes.sections[1]);
+        assert!(fragment.contains("welcome <code>@everyone</code>"));
+    }
+}
@@ -2,85 +2,465 @@ use std::sync::LazyLock;
 
 use regex::Regex;
 
+use crate::repositories::Repository;
+
 #[derive(Debug)]
 pub struct Outcome {
     pub kind: OutcomeKind,
     pub issue: String,
+    /// The URL of the issue as referenced in the minutes, if it has since been transferred or renamed
+    pub moved_from: Option<String>,
 }
 
 #[derive(Debug)]
 pub enum OutcomeKind {
     /// A comment was created for this issue (URL or the comment)
     Created(String),
+    /// A comment was created for this issue, with a truncated transcript (URL or the comment)
+    Truncated(String),
+    /// A comment previously created for the same minutes was updated (URL of the comment)
+    Updated(String),
+    /// A comment previously created for the same minutes was updated, with a truncated transcript
+    /// (URL of the comment)
+    TruncatedUpdate(String),
+    /// A label was added to this issue after commenting it (name of the label)
+    Labeled(String),
+    /// A label was removed from this issue after commenting it (name of the label)
+    Unlabeled(String),
+    /// A label could not be added or removed after commenting this issue (name of the label, and error)
+    LabelError(String, anyhow::Error),
+    /// A comment created by a previous run was deleted (URL of the comment)
+    Deleted(String),
+    /// A comment created by a previous run was not deleted, because it does not carry the marker of this program
+    /// for the same minutes (URL of the comment)
+    Unmarked(String),
     /// A comment was not created because of dry-run mode
     Faked,
     /// This issue was skipped because of a comment pointing to the minutes already exists (URL of the comment)
     Duplicate(String),
     /// This issue was skipped because it is not in a repository owned by the current group(s)
     NotOwned,
+    /// This issue was skipped because it is locked
+    Locked,
+    /// This issue was skipped because its repository is archived (hence read-only)
+    ArchivedRepo,
+    /// This issue does not exist (anymore), or is not visible with the current token
+    NotFound,
+    /// This issue was skipped because it is closed (and closed issues are not to be commented)
+    Closed,
     /// An error occurred
-    #[expect(dead_code)]
     Error(anyhow::Error),
 }
 
+impl OutcomeKind {
+    /// The name of this kind of outcome, in snake case (e.g. `created`, `not_owned`).
+    pub fn name(&self) -> &'static str {
+        use OutcomeKind::*;
+
+        match self {
+            Created(_) => "created",
+            Truncated(_) => "truncated",
+            Updated(_) => "updated",
+            TruncatedUpdate(_) => "truncated_update",
+            Labeled(_) => "labeled",
+            Unlabeled(_) => "unlabeled",
+            LabelError(..) => "label_error",
+            Deleted(_) => "deleted",
+            Unmarked(_) => "unmarked",
+            Faked => "faked",
+            Duplicate(_) => "duplicate",
+            NotOwned => "not_owned",
+            Locked => "locked",
+            ArchivedRepo => "archived_repo",
+            NotFound => "not_found",
+            Closed => "closed",
+            Error(_) => "error",
+        }
+    }
+}
+
 impl Outcome {
     pub fn created(issue: Issue, comment: impl ToString) -> Self {
         Self {
             kind: OutcomeKind::Created(comment.to_string()),
             issue: issue.url.to_string(),
+            moved_from: issue.moved_from,
+        }
+    }
+    pub fn truncated(issue: Issue, comment: impl ToString) -> Self {
+        Self {
+            kind: OutcomeKind::Truncated(comment.to_string()),
+            issue: issue.url.to_string(),
+            moved_from: issue.moved_from,
+        }
+    }
+    pub fn updated(issue: Issue, comment: impl ToString) -> Self {
+        Self {
+            kind: OutcomeKind::Updated(comment.to_string()),
+            issue: issue.url.to_string(),
+            moved_from: issue.moved_from,
+        }
+    }
+    pub fn truncated_update(issue: Issue, comment: impl ToString) -> Self {
+        Self {
+            kind: OutcomeKind::TruncatedUpdate(comment.to_string()),
+            issue: issue.url.to_string(),
+            moved_from: issue.moved_from,
+        }
+    }
+    pub fn labeled(issue: Issue, label: impl ToString) -> Self {
+        Self {
+            kind: OutcomeKind::Labeled(label.to_string()),
+            issue: issue.url.to_string(),
+            moved_from: issue.moved_from,
+        }
+    }
+    pub fn unlabeled(issue: Issue, label: impl ToString) -> Self {
+        Self {
+            kind: OutcomeKind::Unlabeled(label.to_string()),
+            issue: issue.url.to_string(),
+            moved_from: issue.moved_from,
+        }
+    }
+    pub fn deleted(issue: Issue, comment: impl ToString) -> Self {
+        Self {
+            kind: OutcomeKind::Deleted(comment.to_string()),
+            issue: issue.url.to_string(),
+            moved_from: issue.moved_from,
+        }
+    }
+    pub fn unmarked(issue: Issue, comment: impl ToString) -> Self {
+        Self {
+            kind: OutcomeKind::Unmarked(comment.to_string()),
+            issue: issue.url.to_string(),
+            moved_from: issue.moved_from,
         }
     }
     pub fn faked(issue: Issue) -> Self {
         Self {
             kind: OutcomeKind::Faked,
             issue: issue.url.to_string(),
+            moved_from: issue.moved_from,
         }
     }
     pub fn duplicate(issue: Issue, comment: impl ToString) -> Self {
         Self {
             kind: OutcomeKind::Duplicate(comment.to_string()),
             issue: issue.url.to_string(),
+            moved_from: issue.moved_from,
         }
     }
     pub fn not_owned(issue: Issue) -> Self {
         Self {
             kind: OutcomeKind::NotOwned,
             issue: issue.url.to_string(),
+            moved_from: issue.moved_from,
+        }
+    }
+    pub fn locked(issue: Issue) -> Self {
+        Self {
+            kind: OutcomeKind::Locked,
+            issue: issue.url.to_string(),
+            moved_from: issue.moved_from,
+        }
+    }
+    pub fn archived_repo(issue: Issue) -> Self {
+        Self {
+            kind: OutcomeKind::ArchivedRepo,
+            issue: issue.url.to_string(),
+            moved_from: issue.moved_from,
+        }
+    }
+    pub fn not_found(issue: Issue) -> Self {
+        Self {
+            kind: OutcomeKind::NotFound,
+            issue: issue.url.to_string(),
+            moved_from: issue.moved_from,
+        }
+    }
+    pub fn closed(issue: Issue) -> Self {
+        Self {
+            kind: OutcomeKind::Closed,
+            issue: issue.url.to_string(),
+            moved_from: issue.moved_from,
+        }
+    }
+    pub fn label_error(issue: Issue, label: impl ToString, error: anyhow::Error) -> Self {
+        Self {
+            kind: OutcomeKind::LabelError(label.to_string(), error),
+            issue: issue.url.to_string(),
+            moved_from: issue.moved_from,
         }
     }
     pub fn error(issue: Issue, error: anyhow::Error) -> Self {
         Self {
             kind: OutcomeKind::Error(error),
             issue: issue.url.to_string(),
+            moved_from: issue.moved_from,
         }
     }
 }
 
-#[derive(Clone, Copy, Debug)]
-pub struct Issue<'a> {
-    pub url: &'a str,
-    pub owner: &'a str,
-    pub repo: &'a str,
+/// A GitHub issue, pull request or discussion.
+///
+/// Two issues are considered equal if they have the same owner, repository and number,
+/// regardless of the URL used to reference them
+/// (GitHub uses the same sequence of numbers for issues, pull requests and discussions).
+#[derive(Clone, Debug)]
+pub struct Issue {
+    /// The canonical URL of this issue
+    pub url: String,
+    /// The URL (or shorthand) through which this issue was referenced in the minutes
+    pub href: String,
+    pub owner: String,
+    pub repo: String,
     pub id: u64,
+    pub kind: IssueKind,
+    /// The URL of this issue as referenced in the minutes, if it has since been transferred or renamed
+    pub moved_from: Option<String>,
+}
+
+/// The different kinds of [`Issue`]s.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IssueKind {
+    Issue,
+    PullRequest,
+    Discussion,
+}
+
+impl IssueKind {
+    /// The segment used for this kind of issue in GitHub URLs.
+    fn path_segment(self) -> &'static str {
+        match self {
+            Self::Issue => "issues",
+            Self::PullRequest => "pull",
+            Self::Discussion => "discussions",
+        }
+    }
 }
 
-impl<'a> Issue<'a> {
-    pub fn try_from_url(url: &'a str) -> Option<Self> {
+/// The host of the public GitHub web interface.
+pub const GITHUB_HOST: &str = "github.com";
+
+impl Issue {
+    pub fn new(host: &str, owner: &str, repo: &str, id: u64) -> Self {
+        let url = format!("https://{host}/{owner}/{repo}/issues/{id}");
+        Issue {
+            href: url.clone(),
+            url,
+            owner: owner.into(),
+            repo: repo.into(),
+            id,
+            kind: IssueKind::Issue,
+            moved_from: None,
+        }
+    }
+
+    /// Recognize a link to a GitHub issue, pull request or discussion.
+    ///
+    /// Links to a specific part of the issue (e.g. `#issuecomment-99`, `/files`, `/commits`),
+    /// with a query string or a trailing slash, or using the `www.` variant of `host`,
+    /// are all normalized to the canonical URL of the issue.
+    /// Links to any other host than `host` (e.g. [`GITHUB_HOST`]) are ignored.
+    pub fn try_from_url(url: &str, host: &str) -> Option<Self> {
         static RE_ISSUE: LazyLock<Regex> = LazyLock::new(|| {
-            Regex::new(r"//github.com/([^/]+)/([^/]+)/(issues|pull|#)/([0-9]+)$").unwrap()
+            Regex::new(r"^(?:https?:)?//(?:www\.)?([^/?#]+)/([^/?#]+)/([^/?#]+)/(issues|pull|discussions|#)/([0-9]+)(?:[/?#].*)?$").unwrap()
         });
-        let groups = RE_ISSUE.captures(url)?;
+        let groups = RE_ISSUE.captures(url.trim())?;
+        if !groups.get(1).unwrap().as_str().eq_ignore_ascii_case(host) {
+            return None;
+        }
+        let owner = groups.get(2).unwrap().as_str();
+        let repo = groups.get(3).unwrap().as_str();
+        let kind = match groups.get(4).unwrap().as_str() {
+            "pull" => IssueKind::PullRequest,
+            "discussions" => IssueKind::Discussion,
+            _ => IssueKind::Issue,
+        };
+        let id = groups.get(5).unwrap().as_str().parse().ok()?;
         Some(Issue {
-            url,
-            owner: groups.get(1).unwrap().as_str(),
-            repo: groups.get(2).unwrap().as_str(),
-            id: groups.get(4).unwrap().as_str().parse().unwrap(),
+            url: format!("https://{host}/{owner}/{repo}/{}/{id}", kind.path_segment()),
+            href: url.into(),
+            owner: owner.into(),
+            repo: repo.into(),
+            id,
+            kind,
+            moved_from: None,
+        })
+    }
+
+    /// This issue, relocated to `current` (where it has been transferred or renamed to),
+    /// or unchanged if `current` is the same issue
+    /// (except for its kind, which is not known beforehand for shorthands).
+    pub fn moved_to(self, current: Self) -> Self {
+        if current == self {
+            if current.kind == self.kind {
+                return self;
+            }
+            return Issue {
+                url: current.url,
+                kind: current.kind,
+                ..self
+            };
+        }
+        Issue {
+            href: self.href,
+            moved_from: Some(self.url),
+            ..current
+        }
+    }
+
+    /// Iter over all shorthand references to issues in `txt`,
+    /// i.e. `{owner}/{repo}#{id}`, or `#{id}` which is resolved against `default_repository`
+    /// (and ignored if `default_repository` is `None`).
+    /// Those issues are assumed to be hosted on `host`.
+    pub fn find_shorthands<'a>(
+        txt: &'a str,
+        default_repository: Option<&'a Repository>,
+        host: &'a str,
+    ) -> impl Iterator<Item = Self> + 'a {
+        static RE_SHORTHAND: LazyLock<Regex> = LazyLock::new(|| {
+            Regex::new(
+                r"(?:^|[\s(\[,;:])(?:([A-Za-z0-9][A-Za-z0-9-]*)/([A-Za-z0-9._-]+))?#([0-9]+)\b",
+            )
+            .unwrap()
+        });
+        RE_SHORTHAND.captures_iter(txt).filter_map(move |c| {
+            let id = c.get(3).unwrap().as_str().parse().ok()?;
+            match (c.get(1), c.get(2)) {
+                (Some(owner), Some(repo)) => {
+                    Some(Issue::new(host, owner.as_str(), repo.as_str(), id))
+                }
+                _ => {
+                    let Some(repo) = default_repository else {
+                        log::debug!("Ignoring #{id}, no default repository");
+                        return None;
+                    };
+                    Some(Issue::new(host, &repo.owner.login, &repo.name, id))
+                }
+            }
+            .map(|issue| Issue {
+                href: c.get(0).unwrap().as_str().trim_start().into(),
+                ..issue
+            })
         })
     }
 }
 
-impl std::fmt::Display for Issue<'_> {
+impl PartialEq for Issue {
+    fn eq(&self, other: &Self) -> bool {
+        self.owner.eq_ignore_ascii_case(&other.owner)
+            && self.repo.eq_ignore_ascii_case(&other.repo)
+            && self.id == other.id
+    }
+}
+
+impl Eq for Issue {}
+
+impl std::hash::Hash for Issue {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.owner.to_ascii_lowercase().hash(state);
+        self.repo.to_ascii_lowercase().hash(state);
+        self.id.hash(state);
+    }
+}
+
+impl std::fmt::Display for Issue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}/{}#{}", self.owner, self.repo, self.id)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("https://github.com/w3c/foo/issues/12" => Some("https://github.com/w3c/foo/issues/12".into()))]
+    #[test_case("https://github.com/w3c/foo/pull/7" => Some("https://github.com/w3c/foo/pull/7".into()))]
+    #[test_case("https://github.com/w3c/foo/issues/12#issuecomment-99" => Some("https://github.com/w3c/foo/issues/12".into()))]
+    #[test_case("https://github.com/w3c/foo/pull/7/files" => Some("https://github.com/w3c/foo/pull/7".into()))]
+    #[test_case("https://github.com/w3c/foo/pull/7/commits" => Some("https://github.com/w3c/foo/pull/7".into()))]
+    #[test_case("https://github.com/w3c/foo/pull/7?w=1" => Some("https://github.com/w3c/foo/pull/7".into()))]
+    #[test_case("https://github.com/w3c/foo/issues/12/" => Some("https://github.com/w3c/foo/issues/12".into()) ; "trailing slash")]
+    #[test_case("https://www.github.com/w3c/foo/issues/12" => Some("https://github.com/w3c/foo/issues/12".into()))]
+    #[test_case("http://github.com/w3c/foo/issues/12" => Some("https://github.com/w3c/foo/issues/12".into()))]
+    #[test_case("https://github.com/w3c/foo/discussions/3#discussioncomment-5" => Some("https://github.com/w3c/foo/discussions/3".into()))]
+    #[test_case("https://github.com/w3c/foo/issues/12x" => None)]
+    #[test_case("https://github.com/w3c/foo/issues" => None)]
+    #[test_case("https://notgithub.com/w3c/foo/issues/12" => None)]
+    #[test_case("https://github.com.example.org/w3c/foo/issues/12" => None)]
+    fn try_from_url(url: &str) -> Option<String> {
+        Issue::try_from_url(url, GITHUB_HOST).map(|i| i.url)
+    }
+
+    #[test_case("https://ghe.example.org/w3c/foo/pull/7/files" => Some("https://ghe.example.org/w3c/foo/pull/7".into()))]
+    #[test_case("https://GHE.example.org/w3c/foo/issues/12" => Some("https://ghe.example.org/w3c/foo/issues/12".into()))]
+    #[test_case("https://github.com/w3c/foo/issues/12" => None)]
+    fn try_from_url_custom_host(url: &str) -> Option<String> {
+        Issue::try_from_url(url, "ghe.example.org").map(|i| i.url)
+    }
+
+    #[test]
+    fn equality() {
+        let a = Issue::try_from_url(
+            "https://github.com/w3c/foo/issues/12#issuecomment-99",
+            GITHUB_HOST,
+        )
+        .unwrap();
+        let b =
+            Issue::try_from_url("https://www.github.com/W3C/foo/pull/12/", GITHUB_HOST).unwrap();
+        let c = Issue::find_shorthands("w3c/foo#12", None, GITHUB_HOST)
+            .next()
+            .unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a, c);
+        assert_eq!(c.href, "w3c/foo#12");
+    }
+
+    #[test]
+    fn moved_to() {
+        let a = Issue::find_shorthands("w3c/foo#12", None, GITHUB_HOST)
+            .next()
+            .unwrap();
+        let same =
+            Issue::try_from_url("https://github.com/W3C/Foo/issues/12", GITHUB_HOST).unwrap();
+        assert_eq!(a.clone().moved_to(same).moved_from, None);
+        let discussion =
+            Issue::try_from_url("https://github.com/w3c/foo/discussions/12", GITHUB_HOST).unwrap();
+        let resolved = a.clone().moved_to(discussion);
+        assert_eq!(resolved.moved_from, None);
+        assert_eq!(resolved.kind, IssueKind::Discussion);
+        assert_eq!(resolved.url, "https://github.com/w3c/foo/discussions/12");
+        let b = Issue::try_from_url("https://github.com/w3c/bar/pull/3", GITHUB_HOST).unwrap();
+        let moved = a.moved_to(b);
+        assert_eq!(moved.url, "https://github.com/w3c/bar/pull/3");
+        assert_eq!(moved.href, "w3c/foo#12");
+        assert_eq!(moved.kind, IssueKind::PullRequest);
+        assert_eq!(
+            moved.moved_from.as_deref(),
+            Some("https://github.com/w3c/foo/issues/12")
+        );
+    }
+
+    #[test_case("w3c/vc-data-model#1234" => vec!["w3c/vc-data-model#1234"])]
+    #[test_case("see #12 and pchampin/sophia_rs#3." => vec!["w3c/default#12", "pchampin/sophia_rs#3"])]
+    #[test_case("(#12, w3c/foo#13)" => vec!["w3c/default#12", "w3c/foo#13"])]
+    #[test_case("https://example.org/page#12" => Vec::<String>::new())]
+    #[test_case("https://github.com/w3c/foo/issues/12#issuecomment-99" => Vec::<String>::new())]
+    #[test_case("issue#12" => Vec::<String>::new())]
+    fn find_shorthands(txt: &str) -> Vec<String> {
+        let default = Repository::from("default");
+        Issue::find_shorthands(txt, Some(&default), GITHUB_HOST)
+            .map(|i| i.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn find_shorthands_without_default() {
+        let got: Vec<_> = Issue::find_shorthands("#12 and w3c/foo#13", None, GITHUB_HOST)
+            .map(|i| i.to_string())
+            .collect();
+        assert_eq!(got, vec!["w3c/foo#13"]);
+    }
+}
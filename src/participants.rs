@@ -0,0 +1,76 @@
+//! I define the mapping from meeting participants to their GitHub logins.
+
+use std::collections::HashMap;
+
+use crate::error::ParticipantsError;
+
+/// A mapping from IRC nicks or W3C names to GitHub logins, loaded from a JSON object.
+///
+/// Names are matched case-insensitively, ignoring extra whitespace.
+#[derive(Clone, Debug, Default)]
+pub struct Participants {
+    logins: HashMap<String, String>,
+}
+
+impl Participants {
+    pub fn parse(json: &str) -> Result<Self, serde_json::Error> {
+        let map: HashMap<String, String> = serde_json::from_str(json)?;
+        let logins = map
+            .into_iter()
+            .map(|(name, login)| (normalize(&name), login.trim_start_matches('@').to_string()))
+            .collect();
+        Ok(Self { logins })
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, ParticipantsError> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|err| ParticipantsError::File(path.to_string(), err))?;
+        Self::parse(&json).map_err(|err| ParticipantsError::Parse(path.to_string(), err))
+    }
+
+    /// The GitHub logins of the given speakers (ignoring those who are not mapped), without duplicates.
+    pub fn logins<'a>(&'a self, speakers: &[String]) -> Vec<&'a str> {
+        let mut ret: Vec<&str> = vec![];
+        for login in speakers
+            .iter()
+            .filter_map(|s| self.logins.get(&normalize(s)))
+        {
+            if !ret.iter().any(|l| l.eq_ignore_ascii_case(login)) {
+                ret.push(login);
+            }
+        }
+        ret
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn logins() {
+        let participants = Participants::parse(
+            r#"{"manu": "msporny", "Manu Sporny": "msporny", "Ivan  Herman": "@iherman"}"#,
+        )
+        .unwrap();
+        let speakers = [
+            "Manu".into(),
+            "ivan herman".into(),
+            "pchampin".into(),
+            "manu sporny".into(),
+        ];
+        assert_eq!(participants.logins(&speakers), vec!["msporny", "iherman"]);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(Participants::parse(r#"["manu"]"#).is_err());
+    }
+}
@@ -16,7 +16,8 @@ pub struct Repository {
 impl Repository {
     /// Determines whether a given issue is part of this repository
     pub fn contains(&self, issue: &Issue) -> bool {
-        issue.owner == self.owner.login && issue.repo == self.name
+        issue.owner.eq_ignore_ascii_case(&self.owner.login)
+            && issue.repo.eq_ignore_ascii_case(&self.name)
     }
 }
 
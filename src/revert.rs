@@ -0,0 +1,223 @@
+//! I implement the reverting of a run, i.e. the deletion of the comments it created.
+
+use anyhow::{Error, Result};
+use async_stream::try_stream;
+use futures::{Stream, TryStreamExt};
+use octocrab::models::CommentId;
+
+use crate::args::{GitHubArgs, RevertArgs};
+use crate::discussions::Discussion;
+use crate::github::{Clients, GitHub};
+use crate::manual::counts;
+use crate::marker::Marker;
+use crate::outcome::{Issue, IssueKind, Outcome, OutcomeKind};
+use crate::runs::{CreatedComment, RunRecord};
+
+pub async fn command(github_args: GitHubArgs, args: RevertArgs) -> Result<()> {
+    let record = RunRecord::load(&args.runs_dir, &args.run_id)?;
+    let github = GitHub::new(&github_args)?;
+    let outcomes: Vec<Outcome> = revert(&github, &github_args.github_host, &record)
+        .try_collect()
+        .await?;
+    for outcome in &outcomes {
+        println!("{}", report(outcome));
+    }
+    if outcomes.is_empty() {
+        println!("Run {} created no comment", record.run_id);
+    } else {
+        println!("Run {}: {}", record.run_id, counts(&outcomes));
+    }
+    let failed: Vec<bool> = outcomes
+        .iter()
+        .map(|o| matches!(o.kind, OutcomeKind::Error(_)))
+        .collect();
+    let record = reverted(record, &failed);
+    if !record.reverted {
+        println!("Some comments could not be deleted, the run can be reverted again");
+    }
+    record.save(&args.runs_dir)?;
+    Ok(())
+}
+
+/// The record of a run after reverting it, given whether reverting each of its comments failed.
+///
+/// Only the comments that could not be deleted are kept, and the run is reverted if there are none left.
+pub fn reverted(record: RunRecord, failed: &[bool]) -> RunRecord {
+    let comments: Vec<_> = record
+        .comments
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| failed.get(*i).copied().unwrap_or(true))
+        .map(|(_, comment)| comment)
+        .collect();
+    RunRecord {
+        reverted: comments.is_empty(),
+        comments,
+        ..record
+    }
+}
+
+/// A one-line report of the given outcome of reverting a comment.
+fn report(outcome: &Outcome) -> String {
+    let issue = &outcome.issue;
+    match &outcome.kind {
+        OutcomeKind::Deleted(comment) => format!("{issue}: deleted {comment}"),
+        OutcomeKind::Unmarked(comment) => {
+            format!("{issue}: not deleted {comment} (it does not carry the expected marker)")
+        }
+        OutcomeKind::Error(err) => format!("{issue}: failed ({err:#})"),
+        other => format!("{issue}: {}", other.name().replace('_', " ")),
+    }
+}
+
+/// Delete the comments created by the run of the given `record`, and yield an outcome for each of them.
+///
+/// Comments that do not carry the [`Marker`] of this program for the same minutes are left untouched.
+pub fn revert<'a>(
+    github: &'a GitHub,
+    host: &'a str,
+    record: &'a RunRecord,
+) -> impl Stream<Item = Result<Outcome>> + 'a {
+    try_stream! {
+        log::info!("Reverting run {} ({} comments)", record.run_id, record.comments.len());
+        for comment in &record.comments {
+            let Some(issue) = Issue::try_from_url(&comment.issue, host) else {
+                let err = Error::msg(format!("Unrecognized issue URL {}", comment.issue));
+                log::error!("{:?}", err);
+                yield Outcome {
+                    kind: OutcomeKind::Error(err),
+                    issue: comment.issue.clone(),
+                    moved_from: None,
+                };
+                continue;
+            };
+            let clients = match github.clients(&issue.owner, &issue.repo).await {
+                Err(err) => {
+                    log::error!("{:?}", err);
+                    yield Outcome::error(issue, Error::new(err).context("Authenticating"));
+                    continue;
+                }
+                Ok(clients) => clients,
+            };
+            yield delete(&clients, issue, comment, &record.minutes).await;
+        }
+    }
+}
+
+/// Delete the given comment, provided that it carries a marker for the given `minutes`.
+async fn delete(
+    github: &Clients,
+    issue: Issue,
+    comment: &CreatedComment,
+    minutes: &str,
+) -> Outcome {
+    let body = match body(github, &issue, &comment.id).await {
+        Err(err) => {
+            log::error!("{:?}", err);
+            return Outcome::error(issue, err.context("Fetching comment"));
+        }
+        Ok(None) => {
+            log::warn!(
+                "Comment {} not found, it was probably already deleted",
+                comment.url
+            );
+            return Outcome::not_found(issue);
+        }
+        Ok(Some(body)) => body,
+    };
+    if Marker::find(&body).is_none_or(|m| m.minutes != minutes) {
+        log::warn!(
+            "Not deleting {}, it does not carry the expected marker",
+            comment.url
+        );
+        return Outcome::unmarked(issue, &comment.url);
+    }
+    let deleted = if issue.kind == IssueKind::Discussion {
+        Discussion::delete_comment(&github.graphql, &comment.id).await
+    } else {
+        match comment.id.parse() {
+            Err(err) => Err(Error::new(err)),
+            Ok(id) => github
+                .rest
+                .issues(&issue.owner, &issue.repo)
+                .delete_comment(CommentId(id))
+                .await
+                .map_err(Error::new),
+        }
+    };
+    match deleted {
+        Err(err) => {
+            log::error!("{:?}", err);
+            Outcome::error(issue, err.context("Deleting comment"))
+        }
+        Ok(()) => {
+            log::info!("Comment deleted: {}", comment.url);
+            Outcome::deleted(issue, &comment.url)
+        }
+    }
+}
+
+/// The current body of the given comment, or `None` if it does not exist anymore.
+async fn body(github: &Clients, issue: &Issue, id: &str) -> Result<Option<String>> {
+    if issue.kind == IssueKind::Discussion {
+        return Discussion::comment_body(&github.graphql, id).await;
+    }
+    match github
+        .rest
+        .issues(&issue.owner, &issue.repo)
+        .get_comment(CommentId(id.parse()?))
+        .await
+    {
+        Err(octocrab::Error::GitHub { source, .. }) if source.status_code.as_u16() == 404 => {
+            Ok(None)
+        }
+        Err(err) => Err(err.into()),
+        Ok(comment) => Ok(Some(comment.body.unwrap_or_default())),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::outcome::GITHUB_HOST;
+    use test_case::test_case;
+
+    const COMMENT: &str = "https://github.com/w3c/vc/issues/1#issuecomment-42";
+
+    #[test_case(Outcome::deleted(issue(), COMMENT) => format!("https://github.com/w3c/vc/issues/1: deleted {COMMENT}"))]
+    #[test_case(Outcome::unmarked(issue(), COMMENT) => format!("https://github.com/w3c/vc/issues/1: not deleted {COMMENT} (it does not carry the expected marker)"))]
+    #[test_case(Outcome::not_found(issue()) => "https://github.com/w3c/vc/issues/1: not found")]
+    #[test_case(Outcome::error(issue(), Error::msg("403 Forbidden").context("Deleting comment")) => "https://github.com/w3c/vc/issues/1: failed (Deleting comment: 403 Forbidden)")]
+    fn report_line(outcome: Outcome) -> String {
+        report(&outcome)
+    }
+
+    #[test]
+    fn partially_reverted() {
+        let comment = |id: &str| CreatedComment {
+            issue: "https://github.com/w3c/vc/issues/1".into(),
+            id: id.into(),
+            url: format!("https://github.com/w3c/vc/issues/1#issuecomment-{id}"),
+        };
+        let record = RunRecord {
+            run_id: "20241114T100000.000Z".into(),
+            channel: "#vc".into(),
+            minutes: "https://www.w3.org/2024/11/14-vc-minutes.html".into(),
+            date: chrono::NaiveDate::from_ymd_opt(2024, 11, 14).unwrap(),
+            groups: "wg/vc".into(),
+            comments: vec![comment("1"), comment("2"), comment("3")],
+            reverted: false,
+        };
+        let got = reverted(record, &[false, true]);
+        assert!(!got.reverted);
+        let ids: Vec<_> = got.comments.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, ["2", "3"]);
+        let got = reverted(got, &[false, false]);
+        assert!(got.reverted);
+        assert!(got.comments.is_empty());
+    }
+
+    fn issue() -> Issue {
+        Issue::new(GITHUB_HOST, "w3c", "vc", 1)
+    }
+}
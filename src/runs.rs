@@ -0,0 +1,166 @@
+//! I define the records of the comments created by each run, which allow to revert a run.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::error::RunRecordError;
+
+/// The record of a run, stored as `{run_id}.json` in the runs directory.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RunRecord {
+    pub run_id: String,
+    /// The IRC channel of the minutes (starting with '#')
+    pub channel: String,
+    /// The URL of the minutes
+    pub minutes: String,
+    pub date: NaiveDate,
+    pub groups: String,
+    /// The comments created by the run (comments updated in place are not included)
+    pub comments: Vec<CreatedComment>,
+    /// Whether the comments of this run have been deleted
+    #[serde(default)]
+    pub reverted: bool,
+}
+
+/// A comment created by a run.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CreatedComment {
+    /// The URL of the commented issue, pull request or discussion
+    pub issue: String,
+    /// The REST id of an issue comment, or the GraphQL node id of a discussion comment
+    pub id: String,
+    /// The URL of the comment
+    pub url: String,
+}
+
+impl RunRecord {
+    fn path(dir: &str, run_id: &str) -> PathBuf {
+        Path::new(dir).join(format!("{run_id}.json"))
+    }
+
+    /// Load the record of the given run.
+    pub fn load(dir: &str, run_id: &str) -> Result<Self, RunRecordError> {
+        let path = Self::path(dir, run_id);
+        let display = path.display().to_string();
+        let json = std::fs::read_to_string(&path)
+            .map_err(|err| RunRecordError::File(display.clone(), err))?;
+        serde_json::from_str(&json).map_err(|err| RunRecordError::Parse(display, err))
+    }
+
+    /// Load the record of the last run on the given channel that has not been reverted yet, if any.
+    ///
+    /// Records that cannot be read or parsed are skipped (with a warning).
+    pub fn last(dir: &str, channel: &str) -> Result<Option<Self>, RunRecordError> {
+        let entries = match std::fs::read_dir(dir) {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            entries => entries.map_err(|err| RunRecordError::File(dir.to_string(), err))?,
+        };
+        let mut run_ids: Vec<String> = entries
+            .filter_map(|e| e.ok()?.file_name().into_string().ok())
+            .filter_map(|name| Some(name.strip_suffix(".json")?.to_string()))
+            .collect();
+        // run ids are timestamps, so the last run comes first in reverse lexicographic order
+        run_ids.sort_unstable_by(|a, b| b.cmp(a));
+        for run_id in run_ids {
+            let record = match Self::load(dir, &run_id) {
+                Err(err) => {
+                    log::warn!("Skipping run record: {:?}", anyhow::Error::new(err));
+                    continue;
+                }
+                Ok(record) => record,
+            };
+            if record.channel.eq_ignore_ascii_case(channel) && !record.reverted {
+                return Ok(Some(record));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Save this record in the given directory (creating it if needed).
+    pub fn save(&self, dir: &str) -> Result<(), RunRecordError> {
+        std::fs::create_dir_all(dir).map_err(|err| RunRecordError::File(dir.to_string(), err))?;
+        let path = Self::path(dir, &self.run_id);
+        let json = serde_json::to_string_pretty(self).unwrap();
+        std::fs::write(&path, json)
+            .map_err(|err| RunRecordError::File(path.display().to_string(), err))
+    }
+}
+
+/// Keeps the record of the current run up to date in the runs directory.
+#[derive(Debug)]
+pub struct RunRecorder {
+    dir: String,
+    record: Mutex<RunRecord>,
+}
+
+impl RunRecorder {
+    pub fn new(dir: String, record: RunRecord) -> Self {
+        Self {
+            dir,
+            record: Mutex::new(record),
+        }
+    }
+
+    /// Add a comment to the record, and save it immediately
+    /// (so that comments are recorded even if the run is interrupted).
+    pub fn add(&self, comment: CreatedComment) -> Result<(), RunRecordError> {
+        let mut record = self.record.lock().unwrap();
+        record.comments.push(comment);
+        record.save(&self.dir)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn record(run_id: &str, channel: &str) -> RunRecord {
+        RunRecord {
+            run_id: run_id.into(),
+            channel: channel.into(),
+            minutes: "https://www.w3.org/2024/11/14-vc-minutes.html".into(),
+            date: NaiveDate::from_ymd_opt(2024, 11, 14).unwrap(),
+            groups: "wg/vc".into(),
+            comments: vec![],
+            reverted: false,
+        }
+    }
+
+    #[test]
+    fn last() {
+        let dir = std::env::temp_dir().join(format!("m2g-runs-{}", std::process::id()));
+        let dir = dir.to_str().unwrap();
+        assert!(RunRecord::last(dir, "#vc").unwrap().is_none());
+
+        record("20241114T100000.000Z", "#vc").save(dir).unwrap();
+        record("20241114T110000.000Z", "#did").save(dir).unwrap();
+        let recorder = RunRecorder::new(dir.into(), record("20241114T090000.000Z", "#vc"));
+        recorder
+            .add(CreatedComment {
+                issue: "https://github.com/w3c/vc/issues/1".into(),
+                id: "42".into(),
+                url: "https://github.com/w3c/vc/issues/1#issuecomment-42".into(),
+            })
+            .unwrap();
+        std::fs::write(RunRecord::path(dir, "20241114T120000.000Z"), "{").unwrap();
+        let got = RunRecord::last(dir, "#VC").unwrap().unwrap();
+        assert_eq!(got.run_id, "20241114T100000.000Z");
+
+        RunRecord {
+            reverted: true,
+            ..got
+        }
+        .save(dir)
+        .unwrap();
+        let got = RunRecord::last(dir, "#vc").unwrap().unwrap();
+        assert_eq!(got.run_id, "20241114T090000.000Z");
+        assert_eq!(got.comments.len(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,252 @@
+//! I define templates for the comments posted on GitHub, with named placeholders such as `{url}`.
+
+use std::collections::HashMap;
+
+use crate::args::TemplateArgs;
+use crate::error::TemplateError;
+use crate::minutes::ExtractionOptions;
+
+/// A comment template, validated when parsed.
+///
+/// Placeholders are written `{name}`, and literal braces are written `{{` and `}}`.
+/// See [`Placeholder`] for the list of supported placeholders.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// The placeholders supported in [`Template`]s.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Placeholder {
+    /// `{url}`: the link to the (first) relevant section of the minutes (mandatory)
+    Url,
+    /// `{date}`: the date of the meeting
+    Date,
+    /// `{groups}`: the groups concerned by the minutes
+    Groups,
+    /// `{topic}`: the title of the (first) relevant section of the minutes
+    Topic,
+    /// `{channel}`: the IRC channel of the meeting
+    Channel,
+    /// `{sections}`: the list of sections where the issue was discussed (if there are several)
+    Sections,
+    /// `{transcript}`: the transcript of the relevant section, in a `<details>` element (if required)
+    Transcript,
+    /// `{resolutions}`: the list of resolutions and actions of the relevant section (if required)
+    Resolutions,
+    /// `{participants}`: the GitHub users who spoke in the relevant section (if required)
+    Participants,
+    /// `{run_id}`: an identifier of the current run of this program
+    RunId,
+}
+
+impl Placeholder {
+    const ALL: [Self; 10] = [
+        Self::Url,
+        Self::Date,
+        Self::Groups,
+        Self::Topic,
+        Self::Channel,
+        Self::Sections,
+        Self::Transcript,
+        Self::Resolutions,
+        Self::Participants,
+        Self::RunId,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Url => "url",
+            Self::Date => "date",
+            Self::Groups => "groups",
+            Self::Topic => "topic",
+            Self::Channel => "channel",
+            Self::Sections => "sections",
+            Self::Transcript => "transcript",
+            Self::Resolutions => "resolutions",
+            Self::Participants => "participants",
+            Self::RunId => "run_id",
+        }
+    }
+}
+
+impl Template {
+    /// The template used when none is provided.
+    pub const DEFAULT: &str = "This was discussed during the [meeting of {groups} on {date}]({url}).{sections}{resolutions}{participants}{transcript}";
+
+    pub fn parse(text: &str) -> Result<Self, TemplateError> {
+        let mut parts = vec![];
+        let mut literal = String::new();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.next_if_eq(&'{').is_some() => literal.push('{'),
+                '}' if chars.next_if_eq(&'}').is_some() => literal.push('}'),
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(TemplateError::Unclosed(name)),
+                        }
+                    }
+                    let placeholder = Placeholder::ALL
+                        .into_iter()
+                        .find(|p| p.name() == name)
+                        .ok_or(TemplateError::UnknownPlaceholder(name))?;
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Placeholder(placeholder));
+                }
+                '}' => return Err(TemplateError::UnexpectedBrace),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        if !parts.contains(&Part::Placeholder(Placeholder::Url)) {
+            return Err(TemplateError::MissingPlaceholder(Placeholder::Url.name()));
+        }
+        Ok(Self { parts })
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, TemplateError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| TemplateError::File(path.to_string(), err))?;
+        Self::parse(text.trim_end())
+            .map_err(|err| TemplateError::InFile(path.to_string(), Box::new(err)))
+    }
+
+    /// The placeholders of the content requested by `extraction` that this template lacks
+    /// (so that this content would never be rendered).
+    pub fn missing(&self, extraction: &ExtractionOptions) -> Vec<Placeholder> {
+        [
+            (extraction.transcript, Placeholder::Transcript),
+            (extraction.resolutions, Placeholder::Resolutions),
+            (extraction.participants, Placeholder::Participants),
+        ]
+        .into_iter()
+        .filter(|(requested, p)| *requested && !self.parts.contains(&Part::Placeholder(*p)))
+        .map(|(_, p)| p)
+        .collect()
+    }
+
+    /// Render this template, substituting each placeholder with the result of `value`.
+    pub fn render(&self, value: impl Fn(Placeholder) -> String) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(txt) => txt.clone(),
+                Part::Placeholder(p) => value(*p),
+            })
+            .collect()
+    }
+}
+
+impl Default for Template {
+    fn default() -> Self {
+        Self::parse(Self::DEFAULT).unwrap()
+    }
+}
+
+/// The templates to use for a given run, possibly depending on the groups.
+#[derive(Clone, Debug, Default)]
+pub struct Templates {
+    default: Template,
+    per_group: HashMap<String, Template>,
+}
+
+impl Templates {
+    /// Load and validate all the templates specified in `args`.
+    pub fn load(args: &TemplateArgs) -> Result<Self, TemplateError> {
+        let default = match &args.template {
+            Some(path) => Template::from_file(path)?,
+            None => Template::default(),
+        };
+        let per_group = args
+            .group_templates
+            .iter()
+            .map(|(group, path)| Ok((group.clone(), Template::from_file(path)?)))
+            .collect::<Result<_, TemplateError>>()?;
+        Ok(Self { default, per_group })
+    }
+
+    /// The template for the given (comma-separated) groups,
+    /// i.e. the template of the first group having one, or the default template.
+    pub fn for_groups(&self, groups: &str) -> &Template {
+        groups
+            .split(',')
+            .find_map(|g| self.per_group.get(g.trim()))
+            .unwrap_or(&self.default)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use test_case::test_case;
+
+    fn values(p: Placeholder) -> String {
+        match p {
+            Placeholder::Url => "https://example.org/#t01".into(),
+            Placeholder::Topic => "Status list".into(),
+            Placeholder::Transcript => "".into(),
+            p => format!("<{}>", p.name()),
+        }
+    }
+
+    #[test_case(Template::DEFAULT => "This was discussed during the [meeting of <groups> on <date>](https://example.org/#t01).<sections><resolutions><participants>")]
+    #[test_case("[{topic}]({url}) {{literal}}{transcript}" => "[Status list](https://example.org/#t01) {literal}")]
+    #[test_case("{url} in {channel} ({run_id})" => "https://example.org/#t01 in <channel> (<run_id>)")]
+    fn render(template: &str) -> String {
+        Template::parse(template).unwrap().render(values)
+    }
+
+    #[test_case("no url" => "missing placeholder {url}")]
+    #[test_case("{url} {title}" => "unknown placeholder {title}")]
+    #[test_case("{url} {topic" => "unclosed placeholder {topic")]
+    #[test_case("{url} }" => "unexpected '}' (use '}}' for a literal brace)")]
+    fn invalid(template: &str) -> String {
+        Template::parse(template).unwrap_err().to_string()
+    }
+
+    #[test_case(Template::DEFAULT, true => Vec::<&str>::new())]
+    #[test_case("{url}{transcript}", false => Vec::<&str>::new())]
+    #[test_case("{url}{transcript}", true => vec!["resolutions", "participants"])]
+    fn missing(template: &str, requested: bool) -> Vec<&'static str> {
+        let extraction = ExtractionOptions {
+            transcript: true,
+            resolutions: requested,
+            participants: requested,
+            default_repository: None,
+            heuristic: Default::default(),
+            github_host: "github.com".into(),
+        };
+        let template = Template::parse(template).unwrap();
+        template
+            .missing(&extraction)
+            .into_iter()
+            .map(Placeholder::name)
+            .collect()
+    }
+
+    #[test]
+    fn for_groups() {
+        let special = Template::parse("{url}").unwrap();
+        let templates = Templates {
+            default: Template::default(),
+            per_group: [("cg/foo".to_string(), special.clone())].into(),
+        };
+        assert_eq!(templates.for_groups("wg/bar,cg/foo"), &special);
+        assert_eq!(templates.for_groups("wg/bar"), &Template::default());
+    }
+}